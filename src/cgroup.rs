@@ -0,0 +1,394 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const CONTROLLERS: &[&str] = &["memory", "cpu", "pids"];
+const CPU_PERIOD_USEC: u64 = 100_000;
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_max: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_max: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids_max: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        self.memory_max.is_none() && self.cpu_max.is_none() && self.pids_max.is_none()
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(memory_max) = &self.memory_max {
+            // Same syntax memory.max accepts: bytes with an optional K/M/G/T suffix, or "max"
+            let digits = memory_max.trim_end_matches(['K', 'M', 'G', 'T', 'k', 'm', 'g', 't']);
+            let valid = memory_max == "max"
+                || (!digits.is_empty()
+                    && digits.len() + 1 >= memory_max.len()
+                    && digits.chars().all(|c| c.is_ascii_digit()));
+            if !valid {
+                anyhow::bail!(
+                    "Invalid memory_max '{}': expected bytes with an optional K/M/G/T suffix",
+                    memory_max
+                );
+            }
+        }
+        if let Some(cpu_max) = self.cpu_max {
+            if !(cpu_max.is_finite() && cpu_max > 0.0) {
+                anyhow::bail!(
+                    "Invalid cpu_max {}: expected a positive number of CPUs",
+                    cpu_max
+                );
+            }
+        }
+        if self.pids_max == Some(0) {
+            anyhow::bail!("Invalid pids_max 0");
+        }
+        Ok(())
+    }
+
+    // (parameter, controller, interface file, value)
+    fn settings(&self) -> Vec<(&'static str, &'static str, &'static str, String)> {
+        let mut settings = Vec::new();
+        if let Some(memory_max) = &self.memory_max {
+            settings.push(("memory_max", "memory", "memory.max", memory_max.clone()));
+            // Without this the kernel swaps the session out instead of OOM-killing it
+            settings.push(("memory_max", "memory", "memory.swap.max", "0".to_string()));
+        }
+        if let Some(cpu_max) = self.cpu_max {
+            let quota = ((cpu_max * CPU_PERIOD_USEC as f64).round() as u64).max(1000);
+            settings.push((
+                "cpu_max",
+                "cpu",
+                "cpu.max",
+                format!("{} {}", quota, CPU_PERIOD_USEC),
+            ));
+        }
+        if let Some(pids_max) = self.pids_max {
+            settings.push(("pids_max", "pids", "pids.max", pids_max.to_string()));
+        }
+        settings
+    }
+}
+
+fn read_controllers(dir: &Path) -> anyhow::Result<HashSet<String>> {
+    let path = dir.join("cgroup.controllers");
+    let controllers = fs::read_to_string(&path)
+        .map_err(|e| anyhow::anyhow!("{} is not a cgroup v2 directory: {}", dir.display(), e))?;
+    Ok(controllers.split_whitespace().map(str::to_string).collect())
+}
+
+// Members of the cgroup at `path`; readable without holding the Cgroup, e.g. from a blocking task
+pub fn pids(path: &Path) -> Vec<i32> {
+    fs::read_to_string(path.join("cgroup.procs"))
+        .map(|procs| {
+            procs
+                .lines()
+                .filter_map(|l| l.trim().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn is_populated(path: &Path) -> bool {
+    match fs::read_to_string(path.join("cgroup.events")) {
+        Ok(events) => events.lines().any(|l| l == "populated 1"),
+        Err(_) => !pids(path).is_empty(),
+    }
+}
+
+fn kill(path: &Path) {
+    // cgroup.kill needs Linux 5.14; older kernels get a signal per process
+    if write_file(&path.join("cgroup.kill"), "1").is_ok() {
+        return;
+    }
+    #[cfg(unix)]
+    for pid in pids(path) {
+        unsafe {
+            libc::kill(pid, libc::SIGKILL);
+        }
+    }
+}
+
+// Kills everything in the cgroup at `path` and waits up to 2s for it to empty; also usable without the Cgroup
+pub async fn kill_and_wait(path: &Path) {
+    if !is_populated(path) {
+        return;
+    }
+    kill(path);
+    let _ = tokio::time::timeout(std::time::Duration::from_secs(2), async {
+        while is_populated(path) {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+    })
+    .await;
+}
+
+fn write_file(path: &Path, value: &str) -> std::io::Result<()> {
+    OpenOptions::new()
+        .write(true)
+        .open(path)?
+        .write_all(value.as_bytes())
+}
+
+pub struct CgroupRoot {
+    path: PathBuf,
+    controllers: HashSet<String>,
+}
+
+impl CgroupRoot {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        fs::create_dir_all(path).map_err(|e| {
+            anyhow::anyhow!("Failed to create cgroup root {}: {}", path.display(), e)
+        })?;
+        let available = read_controllers(path)?;
+
+        let mut controllers = HashSet::new();
+        for controller in CONTROLLERS.iter().filter(|c| available.contains(**c)) {
+            write_file(&path.join("cgroup.subtree_control"), &format!("+{}", controller)).map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to enable the {} controller in {}: {} (the directory must not contain processes)",
+                    controller,
+                    path.display(),
+                    e
+                )
+            })?;
+            controllers.insert(controller.to_string());
+        }
+
+        // Leftovers from an earlier daemon; cgroups that still hold processes cannot be removed
+        for entry in fs::read_dir(path).into_iter().flatten().flatten() {
+            if entry.file_name().to_string_lossy().starts_with("session-") {
+                let _ = fs::remove_dir(entry.path());
+            }
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            controllers,
+        })
+    }
+
+    pub fn create(&self, session_key: &str, limits: &ResourceLimits) -> anyhow::Result<Cgroup> {
+        let settings = limits.settings();
+        for (param, controller, _, _) in &settings {
+            if !self.controllers.contains(*controller) {
+                anyhow::bail!(
+                    "{} needs the {} controller, which is not available in {}",
+                    param,
+                    controller,
+                    self.path.display()
+                );
+            }
+        }
+
+        let path = self.path.join(format!("session-{}", session_key));
+        match fs::create_dir(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => anyhow::bail!("Failed to create cgroup {}: {}", path.display(), e),
+        }
+        let cgroup = Cgroup::open(&path)?;
+        for (_, _, file, value) in settings {
+            match write_file(&path.join(file), &value) {
+                Ok(()) => {}
+                // Kernels built without swap have no memory.swap.max
+                Err(e) if file == "memory.swap.max" && e.kind() == std::io::ErrorKind::NotFound => {
+                }
+                Err(e) => anyhow::bail!("Failed to set {} to {}: {}", file, value, e),
+            }
+        }
+        Ok(cgroup)
+    }
+}
+
+pub struct Cgroup {
+    path: PathBuf,
+    procs: File,
+    oom_kills: u64,
+}
+
+impl Cgroup {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let procs = OpenOptions::new()
+            .write(true)
+            .open(path.join("cgroup.procs"))
+            .map_err(|e| anyhow::anyhow!("Failed to open cgroup {}: {}", path.display(), e))?;
+        let mut cgroup = Self {
+            path: path.to_path_buf(),
+            procs,
+            oom_kills: 0,
+        };
+        cgroup.oom_kills = cgroup.read_oom_kills();
+        Ok(cgroup)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // The child moves itself in between fork and exec, so nothing it starts can run outside the cgroup
+    #[cfg(unix)]
+    pub fn attach(&self, cmd: &mut tokio::process::Command) {
+        use std::os::unix::io::AsRawFd;
+        let fd = self.procs.as_raw_fd();
+        unsafe {
+            cmd.pre_exec(move || {
+                // Writing 0 to cgroup.procs moves the writing process
+                if libc::write(fd, b"0".as_ptr().cast(), 1) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn attach(&self, _cmd: &mut tokio::process::Command) {}
+
+    pub fn is_populated(&self) -> bool {
+        is_populated(&self.path)
+    }
+
+    pub fn kill(&self) {
+        kill(&self.path)
+    }
+
+    pub async fn kill_and_wait(&self) {
+        kill_and_wait(&self.path).await
+    }
+
+    fn read_key(&self, file: &str, key: &str) -> Option<u64> {
+        let contents = fs::read_to_string(self.path.join(file)).ok()?;
+        contents.lines().find_map(|line| {
+            let (name, value) = line.split_once(' ')?;
+            (name == key).then(|| value.trim().parse().ok()).flatten()
+        })
+    }
+
+    fn read_value(&self, file: &str) -> Option<String> {
+        fs::read_to_string(self.path.join(file))
+            .ok()
+            .map(|v| v.trim().to_string())
+    }
+
+    fn read_oom_kills(&self) -> u64 {
+        self.read_key("memory.events", "oom_kill").unwrap_or(0)
+    }
+
+    // True when the kernel OOM-killed something in this cgroup since the last call
+    pub fn take_oom_kill(&mut self) -> bool {
+        let oom_kills = self.read_oom_kills();
+        let killed = oom_kills > self.oom_kills;
+        self.oom_kills = oom_kills;
+        killed
+    }
+
+    pub fn stats(&self) -> serde_json::Value {
+        let number = |file: &str| self.read_value(file).and_then(|v| v.parse::<u64>().ok());
+        let mut stats = json!({"path": self.path});
+        let fields = [
+            (
+                "memory_current_bytes",
+                number("memory.current").map(|v| json!(v)),
+            ),
+            ("memory_peak_bytes", number("memory.peak").map(|v| json!(v))),
+            (
+                "memory_max",
+                self.read_value("memory.max").map(|v| json!(v)),
+            ),
+            ("cpu_max", self.read_value("cpu.max").map(|v| json!(v))),
+            (
+                "cpu_usage_usec",
+                self.read_key("cpu.stat", "usage_usec").map(|v| json!(v)),
+            ),
+            ("pids_current", number("pids.current").map(|v| json!(v))),
+            ("pids_max", self.read_value("pids.max").map(|v| json!(v))),
+            (
+                "oom_kills",
+                self.read_key("memory.events", "oom_kill").map(|v| json!(v)),
+            ),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                stats[name] = value;
+            }
+        }
+        stats
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        // Fails harmlessly while processes remain, e.g. sessions left running for the next daemon
+        let _ = fs::remove_dir(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory(value: &str) -> ResourceLimits {
+        ResourceLimits {
+            memory_max: Some(value.to_string()),
+            ..ResourceLimits::default()
+        }
+    }
+
+    #[test]
+    fn accepts_memory_max_syntax() {
+        for value in ["max", "1073741824", "512M", "2g", "64K", "1T"] {
+            assert!(memory(value).validate().is_ok(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_memory_max() {
+        for value in ["", "M", "512MB", "1.5G", "-1", "512 M", "lots"] {
+            assert!(memory(value).validate().is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_cpu_and_pids() {
+        for cpu_max in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let limits = ResourceLimits {
+                cpu_max: Some(cpu_max),
+                ..ResourceLimits::default()
+            };
+            assert!(limits.validate().is_err(), "{}", cpu_max);
+        }
+        let limits = ResourceLimits {
+            pids_max: Some(0),
+            ..ResourceLimits::default()
+        };
+        assert!(limits.validate().is_err());
+        let limits = ResourceLimits {
+            cpu_max: Some(0.5),
+            pids_max: Some(64),
+            ..ResourceLimits::default()
+        };
+        assert!(limits.validate().is_ok());
+    }
+
+    #[test]
+    fn cpu_max_becomes_quota_per_period() {
+        let limits = ResourceLimits {
+            cpu_max: Some(1.5),
+            ..ResourceLimits::default()
+        };
+        assert_eq!(limits.settings()[0].3, "150000 100000");
+        // The kernel rejects quotas below 1ms
+        let limits = ResourceLimits {
+            cpu_max: Some(0.001),
+            ..ResourceLimits::default()
+        };
+        assert_eq!(limits.settings()[0].3, "1000 100000");
+    }
+}
@@ -1,11 +1,21 @@
+mod cgroup;
 mod log_buffer;
 mod manager;
 mod port_allocator;
+mod proc_info;
+mod project_config;
+mod readiness;
 mod server_entry;
 mod service;
+mod state_file;
+mod watcher;
 
 use anyhow::Result;
+use cgroup::CgroupRoot;
 use manager::Manager;
+use port_allocator::PortAllocator;
+pub use manager::OrphanPolicy;
+pub use server_entry::MAX_IDLE_TIMEOUT_SECS;
 use rmcp::service::RxJsonRpcMessage;
 use rmcp::transport::async_rw::AsyncRwTransport;
 use rmcp::transport::sse_client::SseClientTransport;
@@ -14,12 +24,16 @@ use rmcp::transport::{io::stdio, Transport};
 use rmcp::RoleServer;
 use serde_json::Value;
 use service::DevManagerService;
+use state_file::StateFile;
 use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
 
-fn inject_cwd_if_start_tool(
+const CWD_TOOLS: &[&str] = &["start", "start_group", "list_services"];
+
+fn inject_client_cwd(
     msg: RxJsonRpcMessage<RoleServer>,
     client_cwd: Option<String>,
 ) -> RxJsonRpcMessage<RoleServer> {
@@ -35,7 +49,7 @@ fn inject_cwd_if_start_tool(
 
     if let Some(params) = msg_value.get_mut("params").and_then(|p| p.as_object_mut()) {
         if let Some(name) = params.get("name").and_then(|n| n.as_str()) {
-            if name == "start" {
+            if CWD_TOOLS.contains(&name) {
                 if let Some(arguments) = params.get_mut("arguments").and_then(|a| a.as_object_mut()) {
                     if !arguments.contains_key("cwd") {
                         arguments.insert("cwd".to_string(), Value::String(client_cwd));
@@ -49,23 +63,117 @@ fn inject_cwd_if_start_tool(
     msg
 }
 
-pub async fn run_daemon(port: u16, idle_timeout_secs: u64) -> Result<()> {
-    let manager = Arc::new(Manager::new(Duration::from_secs(idle_timeout_secs)));
+pub struct DaemonConfig {
+    pub port: u16,
+    pub idle_timeout_secs: u64,
+    pub state_file: Option<PathBuf>,
+    pub orphans: OrphanPolicy,
+    pub port_range_start: u16,
+    pub port_range_end: u16,
+    pub exclude_ports: Vec<u16>,
+    pub cgroup_root: Option<PathBuf>,
+    pub detach_on_exit: bool,
+}
+
+pub async fn run_daemon(config: DaemonConfig) -> Result<()> {
+    let DaemonConfig {
+        port,
+        idle_timeout_secs,
+        state_file,
+        orphans,
+        port_range_start,
+        port_range_end,
+        exclude_ports,
+        cgroup_root,
+        detach_on_exit,
+    } = config;
+
+    // Never hand out the daemon's own port
+    let excluded = exclude_ports.into_iter().chain([port]);
+    let port_allocator = PortAllocator::new(port_range_start, port_range_end, excluded)?;
+    let state_file = state_file
+        .or_else(|| StateFile::default_path(port))
+        .map(StateFile::new);
+    let cgroup_root = cgroup_root.as_deref().map(CgroupRoot::open).transpose()?;
+    let manager = Arc::new(Manager::new(
+        Duration::from_secs(idle_timeout_secs),
+        port_allocator,
+        state_file,
+        cgroup_root,
+    ));
     let bind = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    // Bind first: a second daemon started by mistake must fail before it touches the first one's sessions
+    let server = SseServer::serve(bind).await?;
+    manager.recover(orphans).await?;
 
     println!("MCP daemon listening on {}", bind);
-    let server = SseServer::serve(bind).await?;
 
     let cancel = server.with_service({
         let manager = Arc::clone(&manager);
         move || DevManagerService::new(Arc::clone(&manager))
     });
 
-    tokio::signal::ctrl_c().await?;
+    shutdown_signal().await?;
     cancel.cancel();
+
+    if detach_on_exit {
+        let detached = manager.detach();
+        println!(
+            "Leaving {} sessions running for the next daemon to adopt; their output goes to {}",
+            detached["running"],
+            detached["log_dir"].as_str().unwrap_or_default()
+        );
+        return Ok(());
+    }
+
+    println!("Shutting down, stopping all sessions (interrupt again to exit immediately)");
+    let summary = tokio::select! {
+        summary = manager.shutdown() => summary,
+        _ = shutdown_signal() => {
+            println!("Exiting without waiting for sessions to stop");
+            return Ok(());
+        }
+    };
+    print_shutdown_summary(&summary);
     Ok(())
 }
 
+async fn shutdown_signal() -> Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result?,
+            _ = terminate.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}
+
+fn print_shutdown_summary(summary: &Value) {
+    let sessions = summary["sessions"].as_array().cloned().unwrap_or_default();
+    println!("Stopped {} sessions", sessions.len());
+    for session in &sessions {
+        let mut line = format!(
+            "  {} {}",
+            session["session_key"].as_str().unwrap_or_default(),
+            session["command"].as_str().unwrap_or_default()
+        );
+        if let Some(survivors) = session["survivors"].as_array() {
+            line.push_str(&format!(" ({} processes survived)", survivors.len()));
+        }
+        if let Some(ports) = session["ports_in_use"].as_object() {
+            let ports: Vec<&str> = ports.keys().map(String::as_str).collect();
+            line.push_str(&format!(" (ports still in use: {})", ports.join(", ")));
+        }
+        println!("{}", line);
+    }
+}
+
 pub async fn run_stdio_proxy(daemon_url: &str) -> Result<()> {
     let client_cwd = std::env::current_dir()
         .ok()
@@ -89,7 +197,7 @@ pub async fn run_stdio_proxy(daemon_url: &str) -> Result<()> {
                 msg = stdio_transport.receive() => {
                     match msg {
                         Some(c2s) => {
-                            let modified_msg = inject_cwd_if_start_tool(c2s, client_cwd.clone());
+                            let modified_msg = inject_client_cwd(c2s, client_cwd.clone());
                             let _ = req_tx.send(modified_msg);
                         }
                         None => break,
@@ -1,12 +1,107 @@
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::time::SystemTime;
 
 const MAX_BYTES: usize = 512 * 1024;
 const MAX_TAIL_LINES: usize = 100;
 
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+impl LogStream {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogStream::Stdout => "stdout",
+            LogStream::Stderr => "stderr",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampFormat {
+    Iso,
+    Relative,
+}
+
+impl TimestampFormat {
+    pub fn format(&self, at: SystemTime, origin: SystemTime) -> String {
+        match self {
+            TimestampFormat::Iso => humantime::format_rfc3339_millis(at).to_string(),
+            TimestampFormat::Relative => {
+                let ms = at.duration_since(origin).map(|d| d.as_millis()).unwrap_or(0);
+                format!("+{}ms", ms)
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct LogEvent {
+    pub stream: LogStream,
+    pub line: String,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize, JsonSchema)]
+pub struct LogCursor {
+    #[serde(default)]
+    pub stdout: u64,
+    #[serde(default)]
+    pub stderr: u64,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct LogQuery {
+    pub since: Option<u64>,
+    pub limit: Option<usize>,
+    pub from_start: bool,
+}
+
+pub struct LogRead {
+    pub lines: Vec<LogLine>,
+    pub cursor: u64,
+    pub evicted: u64,
+    pub truncated: bool,
+    forward_from: Option<u64>,
+}
+
+impl LogRead {
+    pub fn text(&self) -> String {
+        self.lines.iter().map(|l| l.text.as_str()).collect()
+    }
+}
+
+pub struct CombinedRead {
+    pub lines: Vec<(LogStream, LogLine)>,
+    pub cursor: LogCursor,
+    pub truncated: bool,
+}
+
+pub struct LogMatch {
+    pub line: u64,
+    pub text: String,
+    pub before: Vec<(u64, String)>,
+    pub after: Vec<(u64, String)>,
+}
+
+#[derive(Clone)]
+pub struct LogLine {
+    pub seq: u64,
+    pub at: SystemTime,
+    pub text: String,
+}
+
 #[derive(Clone)]
 pub struct LogBuffer {
-    logs: VecDeque<String>,
+    logs: VecDeque<LogLine>,
     total_bytes: usize,
+    next_seq: u64,
 }
 
 impl LogBuffer {
@@ -14,31 +109,344 @@ impl LogBuffer {
         Self {
             logs: VecDeque::new(),
             total_bytes: 0,
+            next_seq: 1,
         }
     }
 
     pub fn push(&mut self, line: String) {
         let line_bytes = line.len();
         self.total_bytes += line_bytes;
-        self.logs.push_back(line);
+        self.logs.push_back(LogLine {
+            seq: self.next_seq,
+            at: SystemTime::now(),
+            text: line,
+        });
+        self.next_seq += 1;
 
         while self.total_bytes > MAX_BYTES && !self.logs.is_empty() {
             if let Some(old_line) = self.logs.pop_front() {
-                self.total_bytes -= old_line.len();
+                self.total_bytes -= old_line.text.len();
             }
         }
     }
 
+    // Sequence numbers keep counting so existing cursors stay valid
+    pub fn clear(&mut self) {
+        self.logs.clear();
+        self.total_bytes = 0;
+    }
+
     pub fn tail(&self) -> (String, bool) {
-        let len = self.logs.len();
-        let start = len.saturating_sub(MAX_TAIL_LINES);
-        
-        let mut out = String::new();
-        for line in self.logs.iter().skip(start) {
-            out.push_str(line);
-        }
-        
-        let truncated = len > MAX_TAIL_LINES;
-        (out, truncated)
+        let read = self.read(&LogQuery::default());
+        (read.text(), read.truncated)
+    }
+
+    pub fn read(&self, query: &LogQuery) -> LogRead {
+        let latest = self.next_seq - 1;
+        let first_retained = self.logs.front().map(|l| l.seq).unwrap_or(self.next_seq);
+        let limit = query.limit.unwrap_or(MAX_TAIL_LINES).max(1);
+
+        // Forward reads start after the cursor (or at the oldest retained line)
+        // and report the lines evicted in between; otherwise return the newest lines.
+        let forward_from = match (query.since, query.from_start) {
+            (Some(since), _) => Some(since),
+            (None, true) => Some(0),
+            (None, false) => None,
+        };
+
+        let (start, evicted) = match forward_from {
+            Some(since) => {
+                let start = self.logs.partition_point(|l| l.seq <= since);
+                (start, first_retained.saturating_sub(since.saturating_add(1)))
+            }
+            None => (self.logs.len().saturating_sub(limit), 0),
+        };
+
+        let lines: Vec<LogLine> = self.logs.iter().skip(start).take(limit).cloned().collect();
+        let cursor = lines.last().map(|l| l.seq);
+
+        let truncated = match forward_from {
+            Some(_) => cursor.is_some_and(|c| c < latest),
+            None => start > 0,
+        };
+
+        LogRead {
+            lines,
+            cursor: cursor.unwrap_or(latest),
+            evicted,
+            truncated,
+            forward_from,
+        }
+    }
+
+    pub fn search(&self, re: &Regex, context: usize, max_matches: usize) -> (Vec<LogMatch>, bool) {
+        let numbered = |line: &LogLine| (line.seq, line.text.trim_end_matches('\n').to_string());
+
+        let mut matches = Vec::new();
+        for (idx, line) in self.logs.iter().enumerate() {
+            if !re.is_match(line.text.trim_end_matches('\n')) {
+                continue;
+            }
+            if matches.len() == max_matches {
+                return (matches, true);
+            }
+
+            let before_start = idx.saturating_sub(context);
+            matches.push(LogMatch {
+                line: line.seq,
+                text: line.text.trim_end_matches('\n').to_string(),
+                before: self.logs.range(before_start..idx).map(numbered).collect(),
+                after: self.logs.iter().skip(idx + 1).take(context).map(numbered).collect(),
+            });
+        }
+        (matches, false)
+    }
+
+    pub fn any_match(&self, re: &Regex) -> bool {
+        self.logs
+            .iter()
+            .any(|line| re.is_match(line.text.trim_end_matches('\n')))
+    }
+}
+
+pub fn merge_chronological(stdout: LogRead, stderr: LogRead, limit: Option<usize>) -> CombinedRead {
+    let limit = limit.unwrap_or(MAX_TAIL_LINES).max(1);
+    // Streams with nothing new keep the read's own cursor; otherwise only what is returned counts
+    let resume_from = |read: &LogRead| {
+        read.forward_from
+            .map(|from| if read.lines.is_empty() { read.cursor } else { from })
+    };
+    let forward_from = (resume_from(&stdout), resume_from(&stderr));
+    let mut truncated = stdout.truncated || stderr.truncated;
+    let fallback = LogCursor {
+        stdout: stdout.cursor,
+        stderr: stderr.cursor,
+    };
+
+    let mut lines: Vec<(LogStream, LogLine)> = stdout
+        .lines
+        .into_iter()
+        .map(|l| (LogStream::Stdout, l))
+        .chain(stderr.lines.into_iter().map(|l| (LogStream::Stderr, l)))
+        .collect();
+    lines.sort_by_key(|(_, l)| l.at);
+
+    if lines.len() > limit {
+        truncated = true;
+        match forward_from.0 {
+            Some(_) => lines.truncate(limit),
+            None => {
+                lines.drain(..lines.len() - limit);
+            }
+        }
+    }
+
+    let cursor = match forward_from {
+        (Some(stdout_from), Some(stderr_from)) => {
+            let last_seq = |stream: LogStream, from: u64| {
+                lines
+                    .iter()
+                    .rev()
+                    .find(|(s, _)| *s == stream)
+                    .map(|(_, l)| l.seq)
+                    .unwrap_or(from)
+            };
+            LogCursor {
+                stdout: last_seq(LogStream::Stdout, stdout_from),
+                stderr: last_seq(LogStream::Stderr, stderr_from),
+            }
+        }
+        _ => fallback,
+    };
+
+    CombinedRead {
+        lines,
+        cursor,
+        truncated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    // Lines stamped at the given milliseconds so the merge order is deterministic
+    fn buffer(lines: &[(&str, u64)]) -> LogBuffer {
+        let mut buffer = LogBuffer::new();
+        for (text, ms) in lines {
+            buffer.push(format!("{}\n", text));
+            buffer.logs.back_mut().unwrap().at = UNIX_EPOCH + Duration::from_millis(*ms);
+        }
+        buffer
+    }
+
+    fn texts(read: &LogRead) -> Vec<&str> {
+        read.lines.iter().map(|l| l.text.trim_end()).collect()
+    }
+
+    fn combined_texts(read: &CombinedRead) -> Vec<&str> {
+        read.lines.iter().map(|(_, l)| l.text.trim_end()).collect()
+    }
+
+    fn forward(since: u64, limit: usize) -> LogQuery {
+        LogQuery {
+            since: Some(since),
+            limit: Some(limit),
+            from_start: false,
+        }
+    }
+
+    #[test]
+    fn tail_returns_newest_lines() {
+        let buffer = buffer(&[("a", 1), ("b", 2), ("c", 3)]);
+        let read = buffer.read(&LogQuery {
+            limit: Some(2),
+            ..LogQuery::default()
+        });
+        assert_eq!(texts(&read), ["b", "c"]);
+        assert_eq!(read.cursor, 3);
+        assert!(read.truncated);
+        assert_eq!(read.evicted, 0);
+    }
+
+    #[test]
+    fn forward_read_resumes_from_cursor() {
+        let buffer = buffer(&[("a", 1), ("b", 2), ("c", 3), ("d", 4)]);
+        let first = buffer.read(&forward(0, 2));
+        assert_eq!(texts(&first), ["a", "b"]);
+        assert!(first.truncated);
+
+        let second = buffer.read(&forward(first.cursor, 2));
+        assert_eq!(texts(&second), ["c", "d"]);
+        assert_eq!(second.cursor, 4);
+        assert!(!second.truncated);
+
+        let caught_up = buffer.read(&forward(second.cursor, 2));
+        assert!(caught_up.lines.is_empty());
+        assert_eq!(caught_up.cursor, 4);
+    }
+
+    #[test]
+    fn forward_read_counts_evicted_lines() {
+        let mut buffer = LogBuffer::new();
+        let line = "x".repeat(1023) + "\n";
+        for _ in 0..600 {
+            buffer.push(line.clone());
+        }
+        let first_retained = buffer.logs.front().unwrap().seq;
+        assert!(first_retained > 1);
+
+        let read = buffer.read(&forward(10, 1));
+        assert_eq!(read.lines[0].seq, first_retained);
+        assert_eq!(read.evicted, first_retained - 11);
+    }
+
+    #[test]
+    fn since_beyond_latest_does_not_overflow() {
+        let buffer = buffer(&[("a", 1)]);
+        let read = buffer.read(&forward(u64::MAX, 10));
+        assert!(read.lines.is_empty());
+        assert_eq!(read.evicted, 0);
+        assert_eq!(read.cursor, 1);
+    }
+
+    #[test]
+    fn clear_keeps_cursors_valid() {
+        let mut buffer = buffer(&[("a", 1), ("b", 2), ("c", 3)]);
+        buffer.clear();
+        buffer.push("d\n".to_string());
+
+        // A reader that had seen everything misses nothing
+        let caught_up = buffer.read(&forward(3, 10));
+        assert_eq!(texts(&caught_up), ["d"]);
+        assert_eq!(caught_up.lines[0].seq, 4);
+        assert_eq!(caught_up.evicted, 0);
+
+        // Cleared lines a reader never saw count as evicted
+        let behind = buffer.read(&forward(1, 10));
+        assert_eq!(texts(&behind), ["d"]);
+        assert_eq!(behind.evicted, 2);
+
+        let empty = {
+            let mut buffer = buffer.clone();
+            buffer.clear();
+            buffer.read(&forward(4, 10))
+        };
+        assert!(empty.lines.is_empty());
+        assert_eq!(empty.evicted, 0);
+        assert_eq!(empty.cursor, 4);
+    }
+
+    #[test]
+    fn merge_interleaves_by_time() {
+        let stdout = buffer(&[("out1", 1), ("out2", 3)]);
+        let stderr = buffer(&[("err1", 2), ("err2", 4)]);
+        let merged = merge_chronological(
+            stdout.read(&forward(0, 10)),
+            stderr.read(&forward(0, 10)),
+            Some(10),
+        );
+        assert_eq!(combined_texts(&merged), ["out1", "err1", "out2", "err2"]);
+        assert_eq!((merged.cursor.stdout, merged.cursor.stderr), (2, 2));
+        assert!(!merged.truncated);
+    }
+
+    #[test]
+    fn merge_resumes_after_limit_truncation() {
+        let stdout = buffer(&[("out1", 1), ("out2", 3), ("out3", 5), ("out4", 7)]);
+        let stderr = buffer(&[("err1", 2), ("err2", 4), ("err3", 6), ("err4", 8)]);
+        let read = |cursor: LogCursor| {
+            merge_chronological(
+                stdout.read(&forward(cursor.stdout, 3)),
+                stderr.read(&forward(cursor.stderr, 3)),
+                Some(3),
+            )
+        };
+
+        let mut seen = Vec::new();
+        let mut cursor = LogCursor::default();
+        loop {
+            let merged = read(cursor);
+            seen.extend(combined_texts(&merged).into_iter().map(str::to_string));
+            cursor = merged.cursor;
+            if !merged.truncated {
+                break;
+            }
+        }
+        assert_eq!(
+            seen,
+            ["out1", "err1", "out2", "err2", "out3", "err3", "out4", "err4"]
+        );
+        assert_eq!((cursor.stdout, cursor.stderr), (4, 4));
+    }
+
+    #[test]
+    fn merge_keeps_cursor_of_stream_cut_entirely() {
+        let stdout = buffer(&[("out1", 1), ("out2", 2)]);
+        let stderr = buffer(&[("err1", 3), ("err2", 4)]);
+        let merged = merge_chronological(
+            stdout.read(&forward(0, 2)),
+            stderr.read(&forward(0, 2)),
+            Some(2),
+        );
+        assert_eq!(combined_texts(&merged), ["out1", "out2"]);
+        assert!(merged.truncated);
+        // No stderr line was returned, so the next read must start from the beginning of stderr again
+        assert_eq!((merged.cursor.stdout, merged.cursor.stderr), (2, 0));
+    }
+
+    #[test]
+    fn merge_tail_keeps_newest_lines() {
+        let stdout = buffer(&[("out1", 1), ("out2", 3)]);
+        let stderr = buffer(&[("err1", 2), ("err2", 4)]);
+        let query = LogQuery {
+            limit: Some(3),
+            ..LogQuery::default()
+        };
+        let merged = merge_chronological(stdout.read(&query), stderr.read(&query), Some(3));
+        assert_eq!(combined_texts(&merged), ["err1", "out2", "err2"]);
+        assert!(merged.truncated);
+        assert_eq!((merged.cursor.stdout, merged.cursor.stderr), (2, 2));
     }
 }
@@ -1,4 +1,6 @@
 use clap::{Parser, Subcommand};
+use dev_manager_mcp::{DaemonConfig, OrphanPolicy, MAX_IDLE_TIMEOUT_SECS};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "dev-manager-mcp")]
@@ -14,8 +16,27 @@ enum Command {
     Daemon {
         #[arg(long, env = "PORT", default_value_t = 3009)]
         port: u16,
-        #[arg(long, env = "MCP_IDLE_TIMEOUT", default_value_t = 120)]
+        #[arg(
+            long,
+            env = "MCP_IDLE_TIMEOUT",
+            default_value_t = 120,
+            value_parser = clap::value_parser!(u64).range(..=MAX_IDLE_TIMEOUT_SECS)
+        )]
         idle_timeout: u64,
+        #[arg(long, env = "MCP_STATE_FILE")]
+        state_file: Option<PathBuf>,
+        #[arg(long, env = "MCP_ORPHANS", value_enum, default_value = "adopt")]
+        orphans: OrphanPolicy,
+        #[arg(long, env = "MCP_PORT_RANGE_START", default_value_t = 3010)]
+        port_range_start: u16,
+        #[arg(long, env = "MCP_PORT_RANGE_END", default_value_t = u16::MAX)]
+        port_range_end: u16,
+        #[arg(long, env = "MCP_EXCLUDE_PORTS", value_delimiter = ',')]
+        exclude_ports: Vec<u16>,
+        #[arg(long, env = "MCP_CGROUP_ROOT")]
+        cgroup_root: Option<PathBuf>,
+        #[arg(long, env = "MCP_DETACH_ON_EXIT")]
+        detach_on_exit: bool,
     },
     #[command(about = "Run as STDIO proxy that connects to daemon")]
     Stdio {
@@ -32,8 +53,41 @@ enum Command {
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
-    match cli.command.unwrap_or(Command::Daemon { port: 3009, idle_timeout: 120 }) {
-        Command::Daemon { port, idle_timeout } => dev_manager_mcp::run_daemon(port, idle_timeout).await,
+    match cli.command.unwrap_or(Command::Daemon {
+        port: 3009,
+        idle_timeout: 120,
+        state_file: None,
+        orphans: OrphanPolicy::Adopt,
+        port_range_start: 3010,
+        port_range_end: u16::MAX,
+        exclude_ports: Vec::new(),
+        cgroup_root: None,
+        detach_on_exit: false,
+    }) {
+        Command::Daemon {
+            port,
+            idle_timeout,
+            state_file,
+            orphans,
+            port_range_start,
+            port_range_end,
+            exclude_ports,
+            cgroup_root,
+            detach_on_exit,
+        } => {
+            dev_manager_mcp::run_daemon(DaemonConfig {
+                port,
+                idle_timeout_secs: idle_timeout,
+                state_file,
+                orphans,
+                port_range_start,
+                port_range_end,
+                exclude_ports,
+                cgroup_root,
+                detach_on_exit,
+            })
+            .await
+        }
         Command::Stdio { daemon_url } => dev_manager_mcp::run_stdio_proxy(&daemon_url).await,
     }
 }
@@ -1,16 +1,35 @@
+use crate::cgroup::{Cgroup, CgroupRoot};
+use crate::log_buffer::{self, LogCursor, LogEvent, LogLine, LogQuery, LogStream, TimestampFormat};
 use crate::port_allocator::PortAllocator;
-use crate::server_entry::ServerEntry;
+use crate::proc_info::{self, ProcessTable, UsageTotals};
+use crate::project_config;
+use crate::readiness::{self, ReadyCheck, ReadyOutcome, Readiness};
+use crate::server_entry::{
+    self, port_env_var, ExitInfo, ExitReason, LaunchSpec, RestartPolicy, ServerEntry, StopOptions, StopReport,
+    SESSION_ENV,
+};
+use crate::state_file::{unix_secs, PersistedSession, PersistedState, StateFile};
+use crate::watcher::{self, FileWatcher};
 use rand::{thread_rng, Rng};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
 use serde_json::json;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
-use tokio::process::Command;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{broadcast, mpsc};
 
 type SessionKey = String;
 
 const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
 const EXITED_RETENTION_SECS: u64 = 600;
+const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const FOLLOW_EXIT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_FOLLOW_LINES: usize = 1000;
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+const SHUTTING_DOWN: &str = "Daemon is shutting down";
 
 fn generate_session_key() -> String {
     let mut rng = thread_rng();
@@ -19,9 +38,362 @@ fn generate_session_key() -> String {
         .collect()
 }
 
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum OrphanPolicy {
+    Adopt,
+    Kill,
+}
+
+pub struct TailOptions {
+    pub since: Option<LogCursor>,
+    pub limit: Option<usize>,
+    pub from_start: bool,
+    pub combined: bool,
+    pub timestamps: Option<TimestampFormat>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TopSort {
+    #[default]
+    Memory,
+    Cpu,
+}
+
+pub struct GroupMemberSpec {
+    pub name: String,
+    pub spec: LaunchSpec,
+    pub readiness: Option<Readiness>,
+    pub preferred_port: Option<u16>,
+    pub depends_on: Vec<String>,
+}
+
+struct Reused {
+    session_key: SessionKey,
+    port: u16,
+    ports: BTreeMap<String, u16>,
+    unapplied: Vec<&'static str>,
+}
+
+struct GroupMember {
+    name: String,
+    session_key: SessionKey,
+}
+
 struct ManagerInner {
     servers: HashMap<SessionKey, ServerEntry>,
+    groups: HashMap<SessionKey, Vec<GroupMember>>,
     port_allocator: PortAllocator,
+    state_file: Option<StateFile>,
+    cgroup_root: Option<CgroupRoot>,
+    idle_timeout: Duration,
+    shutting_down: bool,
+}
+
+fn topological_order(members: &[GroupMemberSpec]) -> Result<Vec<usize>, String> {
+    let index: HashMap<&str, usize> = members
+        .iter()
+        .enumerate()
+        .map(|(i, m)| (m.name.as_str(), i))
+        .collect();
+    if index.len() != members.len() {
+        return Err("Group member names must be unique".to_string());
+    }
+    // Every member sees the others' ports as <NAME>_PORT, so names like api-1 and api_1 would clash
+    let mut env_vars: HashMap<String, &str> = HashMap::new();
+    for member in members {
+        let var = port_env_var(&member.name);
+        if let Some(other) = env_vars.insert(var.clone(), &member.name) {
+            return Err(format!("'{}' and '{}' would both be exported as {}", other, member.name, var));
+        }
+    }
+
+    let mut deps = Vec::with_capacity(members.len());
+    for member in members {
+        let mut member_deps = Vec::new();
+        for dep in &member.depends_on {
+            match index.get(dep.as_str()) {
+                Some(&i) => member_deps.push(i),
+                None => return Err(format!("'{}' depends on unknown member '{}'", member.name, dep)),
+            }
+        }
+        deps.push(member_deps);
+    }
+
+    let mut order = Vec::with_capacity(members.len());
+    let mut placed = vec![false; members.len()];
+    while order.len() < members.len() {
+        let next = (0..members.len()).find(|&i| !placed[i] && deps[i].iter().all(|&d| placed[d]));
+        match next {
+            Some(i) => {
+                placed[i] = true;
+                order.push(i);
+            }
+            None => {
+                let cycle: Vec<_> = (0..members.len())
+                    .filter(|&i| !placed[i])
+                    .map(|i| members[i].name.as_str())
+                    .collect();
+                return Err(format!("Dependency cycle between: {}", cycle.join(", ")));
+            }
+        }
+    }
+    Ok(order)
+}
+
+fn exit_status(info: &ExitInfo) -> serde_json::Value {
+    json!({
+        "reason": info.reason,
+        "code": info.code,
+        "signal": info.signal_name().map(|s| json!(s)).or(info.signal.map(|s| json!(s))),
+        "exited_at": humantime::format_rfc3339_seconds(info.exited_at_wall).to_string(),
+        "uptime_secs": info.uptime.as_secs()
+    })
+}
+
+fn add_stop_report(result: &mut serde_json::Value, report: &StopReport) {
+    if !report.survivors.is_empty() {
+        result["survivors"] = json!(report.survivors);
+    }
+    if !report.ports_in_use.is_empty() {
+        result["ports_in_use"] = json!(report.ports_in_use);
+    }
+}
+
+fn add_idle_shutdown(result: &mut serde_json::Value, entry: &ServerEntry, idle_timeout: Duration) {
+    match entry.idle_deadline(idle_timeout) {
+        Some(deadline) => {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            result["idle_shutdown_in_secs"] = json!(remaining.as_secs());
+            result["idle_shutdown_at"] =
+                json!(humantime::format_rfc3339_seconds(SystemTime::now() + remaining).to_string());
+        }
+        None => result["idle_shutdown_in_secs"] = json!(null),
+    }
+    if let Some(timeout) = entry.spec.idle_timeout {
+        result["idle_timeout"] = json!(timeout);
+    }
+    if entry.pinned {
+        result["pinned"] = json!(true);
+    }
+}
+
+// `status` counts as activity, so report the idle deadline it has just pushed back, while
+// idle_secs still shows how long the session went unused before this call
+fn touch_status(key: &str, entry: &mut ServerEntry, idle_timeout: Duration) -> serde_json::Value {
+    let idle_secs = entry.last_activity.elapsed().as_secs();
+    entry.last_activity = Instant::now();
+    let mut result = session_status(key, entry, idle_timeout);
+    result["idle_secs"] = json!(idle_secs);
+    result
+}
+
+fn session_status(key: &str, entry: &ServerEntry, idle_timeout: Duration) -> serde_json::Value {
+    let mut result = json!({
+        "session_key": key,
+        "running": entry.is_running(),
+        "command": entry.spec.command,
+        "cwd": entry.spec.resolved_cwd(),
+        "started_at": humantime::format_rfc3339_seconds(entry.started_at).to_string(),
+        "idle_secs": entry.last_activity.elapsed().as_secs()
+    });
+    if entry.is_running() {
+        let uptime = SystemTime::now().duration_since(entry.started_at).unwrap_or_default();
+        result["uptime_secs"] = json!(uptime.as_secs());
+        result["pid"] = json!(entry.pid);
+        result["pgid"] = json!(entry.pgid());
+        add_idle_shutdown(&mut result, entry, idle_timeout);
+    }
+    if let Some(client) = &entry.client {
+        result["client"] = json!(client);
+    }
+    if entry.port != 0 {
+        result["port"] = json!(entry.port);
+        result["ports"] = json!(entry.ports());
+    }
+    if let Some(service) = &entry.spec.service {
+        result["service"] = json!(service);
+    }
+    if !entry.spec.env.is_empty() {
+        result["env"] = json!(server_entry::redact_env(&entry.spec.env));
+    }
+    if let Some(env_file) = &entry.spec.env_file {
+        result["env_file"] = json!(env_file);
+    }
+    if entry.spec.clean_env {
+        result["clean_env"] = json!(true);
+    }
+    if entry.is_adopted() {
+        result["adopted"] = json!(true);
+    }
+    if entry.is_stopping() {
+        result["restarting"] = json!(true);
+    }
+    if let Some(usage) = entry.usage.current.as_ref().filter(|_| entry.is_running()) {
+        let mut resources = json!(usage.totals);
+        resources["peak"] = json!(entry.usage.peak);
+        resources["process_tree"] = json!(usage.processes);
+        result["resources"] = resources;
+    }
+    if entry.spec.stop_signal.is_some() || entry.spec.stop_timeout.is_some() || entry.spec.pre_stop.is_some() {
+        result["stop"] = json!({
+            "signal": entry.spec.stop_signal.as_deref().unwrap_or("SIGTERM"),
+            "timeout_secs": entry.spec.stop_grace().as_secs(),
+            "pre_stop": entry.spec.pre_stop
+        });
+    }
+    if let Some(cgroup) = &entry.cgroup {
+        result["cgroup"] = cgroup.stats();
+    }
+    if entry.pgid().is_some() {
+        let listening = &entry.usage.listening_ports;
+        let allocated = entry.ports();
+        if !listening.is_empty() {
+            result["port_mismatch"] = json!(!allocated.values().any(|p| listening.contains(p)));
+        }
+        result["listening_ports"] = json!(listening);
+    }
+    if let Some(info) = entry.exit_info() {
+        result["exit"] = exit_status(info);
+    }
+    if entry.spec.restart != RestartPolicy::Never {
+        let mut restart = json!({
+            "policy": entry.spec.restart,
+            "max_restarts": entry.max_restarts(),
+            "count": entry.restarts.count,
+            "pending": entry.restart_pending(),
+            "history": entry.restarts.history.iter().map(exit_status).collect::<Vec<_>>()
+        });
+        if let Some(next_at) = entry.restarts.next_at {
+            restart["next_in_ms"] = json!(next_at.saturating_duration_since(Instant::now()).as_millis() as u64);
+        }
+        result["restart"] = restart;
+    }
+    result
+}
+
+impl ManagerInner {
+    fn generate_key(&self) -> SessionKey {
+        loop {
+            let key = generate_session_key();
+            if !self.servers.contains_key(&key) && !self.groups.contains_key(&key) {
+                break key;
+            }
+        }
+    }
+
+    fn release_ports(&mut self, session_key: &str) -> bool {
+        let ports = match self.servers.get_mut(session_key) {
+            Some(entry) => entry.take_ports(),
+            None => return false,
+        };
+        for port in &ports {
+            self.port_allocator.free(*port);
+        }
+        !ports.is_empty()
+    }
+
+    fn allocate_ports(
+        &mut self,
+        spec: &LaunchSpec,
+        preferred_port: Option<u16>,
+    ) -> anyhow::Result<(u16, BTreeMap<String, u16>, Option<String>)> {
+        let sticky_key = spec.sticky_key();
+        let (port, warning) = self.port_allocator.allocate_sticky(&sticky_key, preferred_port)?;
+        let mut extras = BTreeMap::new();
+        for name in &spec.ports {
+            match self.port_allocator.allocate_sticky(&format!("{}#{}", sticky_key, name), None) {
+                Ok((p, _)) => {
+                    extras.insert(name.clone(), p);
+                }
+                Err(e) => {
+                    self.port_allocator.free(port);
+                    for p in extras.values() {
+                        self.port_allocator.free(*p);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok((port, extras, warning))
+    }
+
+    fn spawn_entry(
+        &self,
+        session_key: &str,
+        spec: LaunchSpec,
+        port: u16,
+        extra_ports: BTreeMap<String, u16>,
+    ) -> anyhow::Result<ServerEntry> {
+        let cgroup = match &self.cgroup_root {
+            Some(root) => Some(root.create(session_key, &spec.limits)?),
+            None if !spec.limits.is_empty() => {
+                anyhow::bail!("Resource limits need the daemon to be started with --cgroup-root")
+            }
+            None => None,
+        };
+        ServerEntry::spawn(session_key, spec, port, extra_ports, cgroup)
+    }
+
+    fn group_of(&self, session_key: &str) -> Option<&SessionKey> {
+        self.groups
+            .iter()
+            .find(|(_, members)| members.iter().any(|m| m.session_key == session_key))
+            .map(|(group_key, _)| group_key)
+    }
+
+    fn find_running(&mut self, spec: &LaunchSpec) -> Option<Reused> {
+        self.servers.iter_mut().find_map(|(key, entry)| {
+            entry.poll_exit();
+            if (entry.is_running() || entry.restart_pending()) && entry.spec.same_launch(spec) {
+                entry.last_activity = Instant::now();
+                Some(Reused {
+                    session_key: key.clone(),
+                    port: entry.port,
+                    ports: entry.ports(),
+                    unapplied: entry.spec.unapplied_settings(spec),
+                })
+            } else {
+                None
+            }
+        })
+    }
+
+    fn persist(&self) {
+        let state_file = match &self.state_file {
+            Some(f) => f,
+            None => return,
+        };
+
+        let sessions = self
+            .servers
+            .iter()
+            .filter_map(|(key, entry)| {
+                let pgid = entry.pgid()?;
+                Some(PersistedSession {
+                    session_key: key.clone(),
+                    pgid,
+                    port: entry.port,
+                    extra_ports: entry.extra_ports.clone(),
+                    spec: entry.spec.clone(),
+                    started_at: unix_secs(entry.started_at),
+                    client: entry.client.clone(),
+                    pinned: entry.pinned,
+                    cgroup: entry.cgroup.as_ref().map(|c| c.path().to_path_buf()),
+                })
+            })
+            .collect();
+
+        let (sticky_ports, sticky_last_used) = self.port_allocator.sticky_ports();
+        let state = PersistedState {
+            sessions,
+            sticky_ports,
+            sticky_last_used,
+        };
+        if let Err(e) = state_file.save(&state) {
+            eprintln!("Failed to write state file {}: {}", state_file.path().display(), e);
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -30,11 +402,21 @@ pub struct Manager {
 }
 
 impl Manager {
-    pub fn new(idle_timeout: Duration) -> Self {
+    pub fn new(
+        idle_timeout: Duration,
+        port_allocator: PortAllocator,
+        state_file: Option<StateFile>,
+        cgroup_root: Option<CgroupRoot>,
+    ) -> Self {
         let manager = Self {
             inner: Arc::new(Mutex::new(ManagerInner {
                 servers: HashMap::new(),
-                port_allocator: PortAllocator::new(3010),
+                groups: HashMap::new(),
+                port_allocator,
+                state_file,
+                cgroup_root,
+                idle_timeout,
+                shutting_down: false,
             })),
         };
 
@@ -42,52 +424,180 @@ impl Manager {
         manager
     }
 
+    pub async fn recover(&self, policy: OrphanPolicy) -> anyhow::Result<()> {
+        let persisted = {
+            let mut guard = self.inner.lock().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+            let persisted = match &guard.state_file {
+                Some(f) => f.load()?,
+                None => return Ok(()),
+            };
+            guard
+                .port_allocator
+                .restore_sticky(persisted.sticky_ports.clone(), &persisted.sticky_last_used);
+            persisted
+        };
+
+        let mut adopted = 0;
+        let mut reaped = 0;
+        for session in persisted.sessions {
+            if !server_entry::group_alive(session.pgid) {
+                if let Some(cgroup) = session.cgroup.as_deref().and_then(|path| Cgroup::open(path).ok()) {
+                    cgroup.kill_and_wait().await;
+                }
+                continue;
+            }
+            if !server_entry::owns_group(session.pgid, &session.session_key) {
+                eprintln!(
+                    "Dropping session {}: process group {} belongs to another process",
+                    session.session_key, session.pgid
+                );
+                continue;
+            }
+
+            match policy {
+                OrphanPolicy::Adopt => {
+                    let watcher = self.file_watcher(&session.session_key, &session.spec).unwrap_or_else(|e| {
+                        eprintln!("Failed to watch files for session {}: {}", session.session_key, e);
+                        None
+                    });
+                    let mut guard = self.inner.lock().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+                    if session.port != 0 {
+                        guard.port_allocator.reserve(session.port);
+                    }
+                    for port in session.extra_ports.values() {
+                        guard.port_allocator.reserve(*port);
+                    }
+                    let started_at = session.started_at();
+                    let cgroup = session.cgroup.as_deref().and_then(|path| Cgroup::open(path).ok());
+                    let mut entry = ServerEntry::adopt(
+                        &session.session_key,
+                        session.pgid,
+                        session.port,
+                        session.extra_ports,
+                        session.spec,
+                        started_at,
+                        cgroup,
+                    );
+                    entry.client = session.client;
+                    entry.pinned = session.pinned;
+                    entry.watcher = watcher;
+                    guard.servers.insert(session.session_key, entry);
+                    adopted += 1;
+                }
+                OrphanPolicy::Kill => {
+                    let (signal, grace) = (session.spec.stop_signal(), session.spec.stop_grace());
+                    server_entry::terminate_tree(session.pgid, &session.session_key, signal, grace).await;
+                    if let Some(cgroup) = session.cgroup.as_deref().and_then(|path| Cgroup::open(path).ok()) {
+                        cgroup.kill_and_wait().await;
+                    }
+                    reaped += 1;
+                }
+            }
+        }
+
+        if adopted > 0 || reaped > 0 {
+            println!("Recovered orphaned sessions: {} re-adopted, {} killed", adopted, reaped);
+        }
+
+        if let Ok(guard) = self.inner.lock() {
+            guard.persist();
+        }
+        Ok(())
+    }
+
     fn start_sweeper(&self, idle_timeout: Duration) {
         let inner = self.inner.clone();
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(5));
+            let mut interval = tokio::time::interval(SWEEP_INTERVAL);
             loop {
                 interval.tick().await;
 
-                let (to_stop, to_prune, _ports_to_free) = {
+                let (to_stop, to_prune, scopes) = {
                     let mut guard = match inner.lock() {
                         Ok(g) => g,
                         Err(_) => continue,
                     };
+                    if guard.shutting_down {
+                        break;
+                    }
 
                     let now = Instant::now();
-                    let idle_threshold = idle_timeout;
                     let retention_threshold = Duration::from_secs(EXITED_RETENTION_SECS);
 
                     let mut to_stop = Vec::new();
                     let mut to_prune = Vec::new();
-                    let mut ports_to_free = Vec::new();
+                    let mut exited = Vec::new();
+                    let mut scopes = Vec::new();
+                    let mut restarted = false;
 
                     for (key, entry) in guard.servers.iter_mut() {
+                        // restart_session owns a session while it stops it
+                        if entry.is_stopping() {
+                            continue;
+                        }
                         entry.poll_exit();
 
-                        if let Some(exited_at) = entry.exited_at() {
-                            if entry.port != 0 {
-                                ports_to_free.push((key.clone(), entry.port));
+                        // Sessions waiting to be restarted keep their ports and are never pruned
+                        if entry.restart_pending() {
+                            if entry.restart_due(now) {
+                                if let Err(e) = entry.respawn() {
+                                    eprintln!("Failed to restart session {}: {}", key, e);
+                                }
+                                restarted = true;
                             }
+                        } else if let Some(exited_at) = entry.exited_at() {
+                            exited.push(key.clone());
                             if now.duration_since(exited_at) > retention_threshold {
                                 to_prune.push(key.clone());
                             }
-                        } else if now.duration_since(entry.last_activity) > idle_threshold {
+                        } else if entry.idle_deadline(idle_timeout).is_some_and(|deadline| now > deadline) {
                             to_stop.push(key.clone());
                         }
-                    }
 
-                    for (key, port) in &ports_to_free {
-                        guard.port_allocator.free(*port);
-                        if let Some(entry) = guard.servers.get_mut(key) {
-                            entry.port = 0;
+                        if let Some(scope) = entry.process_scope() {
+                            scopes.push((key.clone(), scope));
                         }
                     }
 
-                    (to_stop, to_prune, ports_to_free)
+                    let mut freed_any = false;
+                    for key in &exited {
+                        freed_any |= guard.release_ports(key);
+                    }
+
+                    if freed_any || restarted {
+                        guard.persist();
+                    }
+
+                    (to_stop, to_prune, scopes)
                 };
 
+                // /proc scans are blocking and grow with the host's process count, so keep them off the lock
+                // and read the process table once for all sessions
+                let samples = tokio::task::spawn_blocking(move || {
+                    let table = if scopes.iter().any(|(_, scope)| scope.needs_process_table()) {
+                        ProcessTable::snapshot(SESSION_ENV)
+                    } else {
+                        ProcessTable::default()
+                    };
+                    scopes
+                        .into_iter()
+                        .map(|(key, scope)| {
+                            let pids = scope.pids(&table);
+                            (key, scope.pgid, proc_info::processes(&pids), proc_info::listening_ports(&pids))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .await
+                .unwrap_or_default();
+                if let Ok(mut guard) = inner.lock() {
+                    for (key, pgid, processes, listening) in samples {
+                        // Skip sessions that were restarted or stopped while sampling
+                        if let Some(entry) = guard.servers.get_mut(&key).filter(|e| e.pgid() == Some(pgid)) {
+                            entry.usage.record(processes, listening);
+                        }
+                    }
+                }
+
                 for key in to_stop {
                     let entry_opt = {
                         let mut guard = match inner.lock() {
@@ -98,16 +608,25 @@ impl Manager {
                     };
 
                     if let Some(mut entry) = entry_opt {
-                        let port = entry.port;
-                        let _ = entry.stop().await;
-                        entry.port = 0;
+                        if let Ok(report) = entry.stop(ExitReason::IdleTimeout, &StopOptions::default()).await {
+                            if !report.survivors.is_empty() || !report.ports_in_use.is_empty() {
+                                eprintln!(
+                                    "Idle session {} left {} surviving processes and ports {:?} in use",
+                                    key,
+                                    report.survivors.len(),
+                                    report.ports_in_use.keys().collect::<Vec<_>>()
+                                );
+                            }
+                        }
+                        entry.watcher = None;
 
                         let mut guard = match inner.lock() {
                             Ok(g) => g,
                             Err(_) => continue,
                         };
-                        guard.port_allocator.free(port);
-                        guard.servers.insert(key, entry);
+                        guard.servers.insert(key.clone(), entry);
+                        guard.release_ports(&key);
+                        guard.persist();
                     }
                 }
 
@@ -116,108 +635,436 @@ impl Manager {
                         Ok(g) => g,
                         Err(_) => continue,
                     };
-                    if let Some(entry) = guard.servers.remove(&key) {
-                        if entry.port != 0 {
-                            guard.port_allocator.free(entry.port);
-                        }
-                    }
+                    guard.release_ports(&key);
+                    guard.servers.remove(&key);
+                    let ManagerInner { servers, groups, .. } = &mut *guard;
+                    groups.retain(|_, members| members.iter().any(|m| servers.contains_key(&m.session_key)));
                 }
             }
         });
     }
 
-    pub async fn start(&self, command: String, cwd: Option<String>) -> serde_json::Value {
-        let (session_key, port) = {
+    // Stops every session in parallel, each with its own stop settings, and refuses new work from then on
+    pub async fn shutdown(&self) -> serde_json::Value {
+        let entries: Vec<(SessionKey, ServerEntry)> = match self.inner.lock() {
+            Ok(mut guard) => {
+                guard.shutting_down = true;
+                guard.servers.drain().collect()
+            }
+            Err(e) => return json!({"error": format!("Lock error: {}", e)}),
+        };
+
+        let mut stopping = tokio::task::JoinSet::new();
+        for (key, mut entry) in entries {
+            stopping.spawn(async move {
+                entry.poll_exit();
+                let was_running = entry.is_running();
+                let report = entry.stop(ExitReason::Stopped, &StopOptions::default()).await;
+                entry.watcher = None;
+                (key, entry, was_running, report.unwrap_or_default())
+            });
+        }
+
+        let mut sessions = Vec::new();
+        while let Some(joined) = stopping.join_next().await {
+            let Ok((key, entry, was_running, report)) = joined else { continue };
+            if was_running {
+                let mut result = json!({"session_key": key, "command": entry.spec.command});
+                add_stop_report(&mut result, &report);
+                sessions.push(result);
+            }
+            if let Ok(mut guard) = self.inner.lock() {
+                guard.servers.insert(key.clone(), entry);
+                guard.release_ports(&key);
+            }
+        }
+
+        if let Ok(guard) = self.inner.lock() {
+            guard.persist();
+        }
+        json!({"stopped": sessions.len(), "sessions": sessions})
+    }
+
+    // Leaves sessions running for the next daemon to adopt, with their output going to files in log_dir
+    pub fn detach(&self) -> serde_json::Value {
+        let mut guard = match self.inner.lock() {
+            Ok(g) => g,
+            Err(e) => return json!({"error": format!("Lock error: {}", e)}),
+        };
+        guard.shutting_down = true;
+        guard.persist();
+
+        let log_dir = guard
+            .state_file
+            .as_ref()
+            .map(StateFile::log_dir)
+            .unwrap_or_else(|| std::env::temp_dir().join("dev-manager-mcp-logs"));
+        let mut running = 0;
+        for (key, entry) in guard.servers.iter_mut() {
+            entry.poll_exit();
+            if !entry.is_running() {
+                continue;
+            }
+            running += 1;
+            if let Err(e) = entry.relay_output(&log_dir) {
+                eprintln!("Failed to keep output of session {} flowing: {}", key, e);
+            }
+        }
+        json!({"running": running, "log_dir": log_dir})
+    }
+
+    pub async fn start(
+        &self,
+        spec: LaunchSpec,
+        readiness: Option<Readiness>,
+        preferred_port: Option<u16>,
+        reuse: bool,
+        client: Option<String>,
+    ) -> serde_json::Value {
+        let (session_key, port, ports, reused, warning, watch_spec) = {
             let mut guard = match self.inner.lock() {
                 Ok(g) => g,
                 Err(e) => return json!({"error": format!("Lock error: {}", e)}),
             };
 
-            let session_key = loop {
-                let key = generate_session_key();
-                if !guard.servers.contains_key(&key) {
-                    break key;
-                }
-            };
+            if guard.shutting_down {
+                return json!({"error": SHUTTING_DOWN});
+            }
 
-            let port = match guard.port_allocator.allocate() {
-                Ok(p) => p,
-                Err(e) => return json!({"error": format!("Port allocation failed: {}", e)}),
-            };
+            let existing = if reuse { guard.find_running(&spec) } else { None };
+            match existing {
+                Some(reused) => {
+                    let warning = (!reused.unapplied.is_empty()).then(|| {
+                        format!(
+                            "Reused a running session launched with different {}; those settings were not applied (stop it or pass reuse: false)",
+                            reused.unapplied.join(", ")
+                        )
+                    });
+                    (reused.session_key, reused.port, reused.ports, true, warning, None)
+                }
+                None => {
+                    let session_key = guard.generate_key();
 
-            (session_key, port)
-        };
+                    let (port, extra_ports, warning) = match guard.allocate_ports(&spec, preferred_port) {
+                        Ok(p) => p,
+                        Err(e) => return json!({"error": format!("Port allocation failed: {}", e)}),
+                    };
 
-        let mut cmd = if cfg!(target_os = "windows") {
-            let mut c = Command::new("cmd");
-            c.arg("/C").arg(&command);
-            c
-        } else {
-            #[cfg(unix)]
-            {
-                let mut c = Command::new("sh");
-                c.arg("-c").arg(&command);
-                unsafe {
-                    #[allow(unused_imports)]
-                    use std::os::unix::process::CommandExt;
-                    c.pre_exec(|| {
-                        if libc::setsid() == -1 {
-                            return Err(std::io::Error::last_os_error());
+                    let mut entry = match guard.spawn_entry(&session_key, spec, port, extra_ports.clone()) {
+                        Ok(entry) => entry,
+                        Err(e) => {
+                            guard.port_allocator.free(port);
+                            for p in extra_ports.values() {
+                                guard.port_allocator.free(*p);
+                            }
+                            return json!({"error": e.to_string()});
                         }
-                        Ok(())
-                    });
+                    };
+                    entry.client = client;
+                    let watch_spec = (!entry.spec.watch.is_empty()).then(|| entry.spec.clone());
+                    let ports = entry.ports();
+                    guard.servers.insert(session_key.clone(), entry);
+                    guard.persist();
+
+                    (session_key, port, ports, false, warning, watch_spec)
                 }
-                c
-            }
-            #[cfg(not(unix))]
-            {
-                let mut c = Command::new("sh");
-                c.arg("-c").arg(&command);
-                c
             }
         };
-        cmd.stdout(std::process::Stdio::piped());
-        cmd.stderr(std::process::Stdio::piped());
 
-        if let Some(ref cwd_path) = cwd {
-            let path = std::path::Path::new(cwd_path);
-            if !path.is_dir() {
-                let mut guard = self.inner.lock().unwrap();
-                guard.port_allocator.free(port);
-                return json!({"error": format!("Invalid cwd: {}", cwd_path)});
+        let mut result = json!({
+            "status": "started",
+            "port": port,
+            "session_key": session_key,
+            "reused": reused
+        });
+        if ports.len() > 1 {
+            result["ports"] = json!(ports);
+        }
+        if let Some(warning) = warning {
+            result["warning"] = json!(warning);
+        }
+        if let Some(Err(e)) = watch_spec.map(|spec| self.watch_session(&session_key, &spec)) {
+            result["watch_error"] = json!(e.to_string());
+        }
+
+        if let Some(readiness) = readiness {
+            self.report_readiness(&mut result, &session_key, port, &readiness).await;
+        }
+
+        result
+    }
+
+    // Watches a registered session's files; the watcher is only kept if the session is still running by then
+    fn watch_session(&self, session_key: &str, spec: &LaunchSpec) -> anyhow::Result<()> {
+        let Some(file_watcher) = self.file_watcher(session_key, spec)? else {
+            return Ok(());
+        };
+        let mut guard = self.inner.lock().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+        if let Some(entry) = guard.servers.get_mut(session_key) {
+            if entry.is_running() && entry.watcher.is_none() {
+                entry.watcher = Some(file_watcher);
             }
-            cmd.current_dir(path);
         }
+        Ok(())
+    }
 
-        cmd.env("PORT", port.to_string());
+    // Registering the watches walks the watched trees, so this must not run under the lock
+    fn file_watcher(&self, session_key: &str, spec: &LaunchSpec) -> anyhow::Result<Option<FileWatcher>> {
+        if spec.watch.is_empty() {
+            return Ok(None);
+        }
+        let (file_watcher, mut changes) = watcher::watch(&spec.resolved_cwd(), &spec.watch)?;
 
-        let child = match cmd.spawn() {
-            Ok(c) => c,
-            Err(e) => {
-                let mut guard = self.inner.lock().unwrap();
-                guard.port_allocator.free(port);
-                return json!({"error": format!("Failed to spawn process: {}", e)});
+        let manager = self.clone();
+        let session_key = session_key.to_string();
+        tokio::spawn(async move {
+            // Ends when the watcher is dropped with its session
+            while let Some(mut changed) = changes.recv().await {
+                // Editors and builds touch several files per save; wait for a quiet period
+                loop {
+                    match tokio::time::timeout(WATCH_DEBOUNCE, changes.recv()).await {
+                        Ok(Some(path)) => changed = path,
+                        Ok(None) => return,
+                        Err(_) => break,
+                    }
+                }
+                let reason = format!("{} changed", changed.display());
+                manager.restart_session(session_key.clone(), false, None, &reason).await;
+                while changes.try_recv().is_ok() {}
             }
-        };
+        });
+        Ok(Some(file_watcher))
+    }
 
-        let entry = ServerEntry::new(child, port);
+    async fn report_readiness(
+        &self,
+        result: &mut serde_json::Value,
+        session_key: &str,
+        port: u16,
+        readiness: &Readiness,
+    ) -> bool {
+        match self.wait_ready(session_key, port, readiness).await {
+            ReadyOutcome::Ready(elapsed) => {
+                result["ready"] = json!(true);
+                result["ready_after_ms"] = json!(elapsed.as_millis() as u64);
+                true
+            }
+            outcome => {
+                result["ready"] = json!(false);
+                result["ready_error"] = json!(match outcome {
+                    ReadyOutcome::Exited => "Process exited before becoming ready".to_string(),
+                    _ => format!("Not ready after {}s", readiness.timeout.as_secs()),
+                });
+                if let Some((stdout, stderr)) = self.logs(session_key) {
+                    result["stdout"] = json!(stdout);
+                    result["stderr"] = json!(stderr);
+                }
+                false
+            }
+        }
+    }
 
-        {
+    pub async fn start_group(&self, members: Vec<GroupMemberSpec>, client: Option<String>) -> serde_json::Value {
+        let order = match topological_order(&members) {
+            Ok(o) => o,
+            Err(e) => return json!({"error": e}),
+        };
+
+        let (group_key, ports) = {
             let mut guard = match self.inner.lock() {
                 Ok(g) => g,
                 Err(e) => return json!({"error": format!("Lock error: {}", e)}),
             };
-            guard.servers.insert(session_key.clone(), entry);
+            if guard.shutting_down {
+                return json!({"error": SHUTTING_DOWN});
+            }
+
+            let mut ports = Vec::with_capacity(members.len());
+            for member in &members {
+                match guard.allocate_ports(&member.spec, member.preferred_port) {
+                    Ok(p) => ports.push(p),
+                    Err(e) => {
+                        for (port, extra_ports, _) in ports {
+                            guard.port_allocator.free(port);
+                            for p in extra_ports.values() {
+                                guard.port_allocator.free(*p);
+                            }
+                        }
+                        return json!({"error": format!("Port allocation failed: {}", e)});
+                    }
+                }
+            }
+            (guard.generate_key(), ports)
+        };
+
+        let port_env: Vec<(String, String)> = members
+            .iter()
+            .zip(&ports)
+            .map(|(m, (port, _, _))| (port_env_var(&m.name), port.to_string()))
+            .collect();
+
+        let mut pending: Vec<Option<GroupMemberSpec>> = members.into_iter().map(Some).collect();
+        let mut started: Vec<GroupMember> = Vec::new();
+        let mut results = Vec::new();
+        let mut failed = None;
+
+        for idx in order {
+            let mut spec = match &pending[idx] {
+                Some(m) => m.spec.clone(),
+                None => continue,
+            };
+            let (port, extra_ports, warning) = ports[idx].clone();
+            spec.env.extend(port_env.iter().cloned());
+
+            // Waiting for earlier members can take a while; shutdown may have drained the sessions meanwhile
+            let spawned = match self.inner.lock() {
+                Ok(guard) if guard.shutting_down => Err(anyhow::anyhow!(SHUTTING_DOWN)),
+                Ok(mut guard) => {
+                    let session_key = guard.generate_key();
+                    guard.spawn_entry(&session_key, spec, port, extra_ports).map(|mut entry| {
+                        entry.client = client.clone();
+                        guard.servers.insert(session_key.clone(), entry);
+                        guard.persist();
+                        session_key
+                    })
+                }
+                Err(e) => Err(anyhow::anyhow!("Lock error: {}", e)),
+            };
+
+            let member = match spawned {
+                Ok(key) => pending[idx].take().map(|m| (m, key)),
+                Err(e) => {
+                    let name = pending[idx].as_ref().map(|m| m.name.clone()).unwrap_or_default();
+                    results.push(json!({"name": name, "port": port, "error": e.to_string()}));
+                    failed = Some(name);
+                    break;
+                }
+            };
+            let Some((member, session_key)) = member else { continue };
+            if let Err(e) = self.watch_session(&session_key, &member.spec) {
+                eprintln!("Failed to watch files for session {}: {}", session_key, e);
+            }
+
+            let mut result = json!({"name": member.name, "session_key": session_key, "port": port});
+            if let Some(warning) = warning {
+                result["warning"] = json!(warning);
+            }
+            started.push(GroupMember {
+                name: member.name.clone(),
+                session_key: session_key.clone(),
+            });
+            let ready = match &member.readiness {
+                Some(readiness) => self.report_readiness(&mut result, &session_key, port, readiness).await,
+                None => true,
+            };
+            results.push(result);
+            if !ready {
+                failed = Some(member.name);
+                break;
+            }
+        }
+
+        if let Some(name) = failed {
+            for member in started.iter().rev() {
+                self.stop_session(member.session_key.clone(), &StopOptions::default()).await;
+            }
+            if let Ok(mut guard) = self.inner.lock() {
+                for (idx, member) in pending.iter().enumerate() {
+                    if member.is_some() {
+                        let (port, extra_ports, _) = &ports[idx];
+                        guard.port_allocator.free(*port);
+                        for p in extra_ports.values() {
+                            guard.port_allocator.free(*p);
+                        }
+                    }
+                }
+            }
+            return json!({
+                "error": format!("Group member '{}' failed to start; started members were stopped", name),
+                "members": results
+            });
+        }
+
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.groups.insert(group_key.clone(), started);
         }
 
         json!({
             "status": "started",
-            "port": port,
-            "session_key": session_key
+            "group_key": group_key,
+            "members": results
         })
     }
 
-    pub async fn stop(&self, session_key: String) -> serde_json::Value {
+    async fn wait_ready(&self, session_key: &str, port: u16, readiness: &Readiness) -> ReadyOutcome {
+        let started = Instant::now();
+        loop {
+            let log_matched = {
+                let mut guard = match self.inner.lock() {
+                    Ok(g) => g,
+                    Err(_) => return ReadyOutcome::Exited,
+                };
+                let entry = match guard.servers.get_mut(session_key) {
+                    Some(e) => e,
+                    None => return ReadyOutcome::Exited,
+                };
+                entry.poll_exit();
+                if !entry.is_running() {
+                    return ReadyOutcome::Exited;
+                }
+                match &readiness.check {
+                    ReadyCheck::LogPattern(re) => entry.logs_match(re),
+                    _ => false,
+                }
+            };
+
+            let ready = match &readiness.check {
+                ReadyCheck::Tcp => readiness::tcp_ready(port).await,
+                ReadyCheck::Http(path) => readiness::http_ready(port, path).await,
+                ReadyCheck::LogPattern(_) => log_matched,
+            };
+            if ready {
+                return ReadyOutcome::Ready(started.elapsed());
+            }
+
+            if started.elapsed() >= readiness.timeout {
+                return ReadyOutcome::TimedOut;
+            }
+            tokio::time::sleep(READY_POLL_INTERVAL).await;
+        }
+    }
+
+    fn logs(&self, session_key: &str) -> Option<(String, String)> {
+        let guard = self.inner.lock().ok()?;
+        guard.servers.get(session_key).map(|entry| entry.get_logs())
+    }
+
+    pub async fn stop(&self, session_key: String, options: StopOptions) -> serde_json::Value {
+        let members: Option<Vec<(String, SessionKey)>> = match self.inner.lock() {
+            Ok(guard) => guard.groups.get(&session_key).map(|members| {
+                members
+                    .iter()
+                    .map(|m| (m.name.clone(), m.session_key.clone()))
+                    .collect()
+            }),
+            Err(e) => return json!({"error": format!("Lock error: {}", e)}),
+        };
+
+        match members {
+            Some(members) => {
+                let mut results = Vec::new();
+                for (name, member_key) in members.into_iter().rev() {
+                    let mut result = self.stop_session(member_key, &options).await;
+                    result["name"] = json!(name);
+                    results.push(result);
+                }
+                json!({"status": "stopped", "group_key": session_key, "members": results})
+            }
+            None => self.stop_session(session_key, &options).await,
+        }
+    }
+
+    async fn stop_session(&self, session_key: String, options: &StopOptions) -> serde_json::Value {
         let mut entry = {
             let mut guard = match self.inner.lock() {
                 Ok(g) => g,
@@ -230,24 +1077,251 @@ impl Manager {
             }
         };
 
-        let port = entry.port;
         entry.last_activity = Instant::now();
 
-        match entry.stop().await {
-            Ok(_) => {
-                entry.port = 0;
-
+        match entry.stop(ExitReason::Stopped, options).await {
+            Ok(report) => {
+                entry.watcher = None;
                 let mut guard = match self.inner.lock() {
                     Ok(g) => g,
                     Err(e) => return json!({"error": format!("Lock error: {}", e)}),
                 };
 
-                guard.port_allocator.free(port);
                 guard.servers.insert(session_key.clone(), entry);
+                guard.release_ports(&session_key);
+                guard.persist();
+
+                let mut result = json!({"status": "stopped", "session_key": session_key});
+                add_stop_report(&mut result, &report);
+                result
+            }
+            Err(e) => {
+                if let Ok(mut guard) = self.inner.lock() {
+                    guard.servers.insert(session_key, entry);
+                }
+                json!({"error": format!("Failed to stop server: {}", e)})
+            }
+        }
+    }
+
+    pub async fn restart(
+        &self,
+        session_key: String,
+        clear_logs: bool,
+        readiness: Option<Readiness>,
+    ) -> serde_json::Value {
+        let members: Option<Vec<(String, SessionKey)>> = match self.inner.lock() {
+            Ok(guard) => guard.groups.get(&session_key).map(|members| {
+                members
+                    .iter()
+                    .map(|m| (m.name.clone(), m.session_key.clone()))
+                    .collect()
+            }),
+            Err(e) => return json!({"error": format!("Lock error: {}", e)}),
+        };
+
+        match members {
+            Some(members) => {
+                let mut results = Vec::new();
+                for (name, member_key) in members {
+                    let mut result = self
+                        .restart_session(member_key, clear_logs, readiness.as_ref(), "restart requested")
+                        .await;
+                    result["name"] = json!(name);
+                    results.push(result);
+                }
+                json!({"status": "restarted", "group_key": session_key, "members": results})
+            }
+            None => {
+                self.restart_session(session_key, clear_logs, readiness.as_ref(), "restart requested")
+                    .await
+            }
+        }
+    }
+
+    async fn restart_session(
+        &self,
+        session_key: String,
+        clear_logs: bool,
+        readiness: Option<&Readiness>,
+        reason: &str,
+    ) -> serde_json::Value {
+        // The entry stays listed, reporting "restarting", while the old process is stopped without the lock
+        let job = {
+            let mut guard = match self.inner.lock() {
+                Ok(g) => g,
+                Err(e) => return json!({"error": format!("Lock error: {}", e)}),
+            };
+
+            let entry = match guard.servers.get_mut(&session_key) {
+                Some(entry) => entry,
+                None => return json!({"error": "Session not found"}),
+            };
+            if entry.is_stopping() {
+                return json!({"error": "Session is already restarting", "session_key": session_key});
+            }
+            entry.last_activity = Instant::now();
+            match entry.begin_stop(&StopOptions::default()) {
+                Ok(job) => job,
+                Err(e) => return json!({"error": format!("Failed to stop server: {}", e)}),
+            }
+        };
+        let stopped = match job {
+            Some(job) => Some(job.run().await),
+            None => None,
+        };
+
+        let (port, ports, report, watch_spec) = {
+            let mut guard = match self.inner.lock() {
+                Ok(g) => g,
+                Err(e) => return json!({"error": format!("Lock error: {}", e)}),
+            };
+            let mut entry = match guard.servers.remove(&session_key) {
+                Some(entry) => entry,
+                None => return json!({"error": "Session was stopped while restarting", "session_key": session_key}),
+            };
+            let report = match stopped {
+                Some(stopped) if entry.is_stopping() => entry.finish_stop(stopped, ExitReason::Stopped),
+                Some(_) => {
+                    guard.servers.insert(session_key.clone(), entry);
+                    return json!({"error": "Session was stopped while restarting", "session_key": session_key});
+                }
+                None => StopReport::default(),
+            };
+            if guard.shutting_down {
+                guard.servers.insert(session_key.clone(), entry);
+                guard.release_ports(&session_key);
+                return json!({"error": SHUTTING_DOWN});
+            }
+
+            // Ports are released once a session has exited; sticky allocation usually hands the same ones back
+            if entry.port == 0 {
+                match guard.allocate_ports(&entry.spec, None) {
+                    Ok((port, extra_ports, _)) => {
+                        entry.port = port;
+                        entry.extra_ports = extra_ports;
+                    }
+                    Err(e) => {
+                        guard.servers.insert(session_key.clone(), entry);
+                        guard.persist();
+                        return json!({"error": format!("Port allocation failed: {}", e)});
+                    }
+                }
+            }
+
+            let restarted = entry.restart(clear_logs, reason);
+            let unwatched = restarted.is_ok() && entry.watcher.is_none() && !entry.spec.watch.is_empty();
+            let watch_spec = unwatched.then(|| entry.spec.clone());
+            let (port, ports) = (entry.port, entry.ports());
+            guard.servers.insert(session_key.clone(), entry);
+            if let Err(e) = restarted {
+                guard.release_ports(&session_key);
+                guard.persist();
+                return json!({"error": e.to_string(), "session_key": session_key});
+            }
+            guard.persist();
+            (port, ports, report, watch_spec)
+        };
+        if let Err(e) = watch_spec.map_or(Ok(()), |spec| self.watch_session(&session_key, &spec)) {
+            eprintln!("Failed to watch files for session {}: {}", session_key, e);
+        }
+
+        let mut result = json!({
+            "status": "restarted",
+            "port": port,
+            "session_key": session_key
+        });
+        if ports.len() > 1 {
+            result["ports"] = json!(ports);
+        }
+        add_stop_report(&mut result, &report);
+
+        if let Some(readiness) = readiness {
+            self.report_readiness(&mut result, &session_key, port, readiness).await;
+        }
+
+        result
+    }
+
+    pub fn top(&self, sort: TopSort, limit: Option<usize>) -> serde_json::Value {
+        let guard = match self.inner.lock() {
+            Ok(g) => g,
+            Err(e) => return json!({"error": format!("Lock error: {}", e)}),
+        };
+
+        let mut rows: Vec<(&SessionKey, &ServerEntry, UsageTotals)> = guard
+            .servers
+            .iter()
+            .filter(|(_, entry)| entry.is_running())
+            .filter_map(|(key, entry)| Some((key, entry, entry.usage.current.as_ref()?.totals)))
+            .collect();
+        match sort {
+            TopSort::Memory => rows.sort_by_key(|row| std::cmp::Reverse(row.2.rss_bytes)),
+            TopSort::Cpu => rows.sort_by(|a, b| b.2.cpu_percent.total_cmp(&a.2.cpu_percent)),
+        }
+
+        let sessions: Vec<_> = rows
+            .into_iter()
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|(key, entry, totals)| {
+                let mut row = json!(totals);
+                row["session_key"] = json!(key);
+                row["command"] = json!(entry.spec.command);
+                row["cwd"] = json!(entry.spec.resolved_cwd());
+                row["peak_rss_bytes"] = json!(entry.usage.peak.rss_bytes);
+                row["peak_cpu_percent"] = json!(entry.usage.peak.cpu_percent);
+                row
+            })
+            .collect();
+        json!({"sessions": sessions})
+    }
+
+    pub fn keepalive(&self, session_key: String, extend: Option<Duration>, pin: Option<bool>) -> serde_json::Value {
+        let mut guard = match self.inner.lock() {
+            Ok(g) => g,
+            Err(e) => return json!({"error": format!("Lock error: {}", e)}),
+        };
+        let idle_timeout = guard.idle_timeout;
+        let keys: Vec<SessionKey> = match guard.groups.get(&session_key) {
+            Some(members) => members.iter().map(|m| m.session_key.clone()).collect(),
+            None => vec![session_key.clone()],
+        };
+
+        let mut results = Vec::new();
+        for key in keys {
+            let entry = match guard.servers.get_mut(&key) {
+                Some(entry) => entry,
+                None => {
+                    results.push(json!({"session_key": key, "error": "Session not found"}));
+                    continue;
+                }
+            };
+            entry.poll_exit();
+            if !entry.is_running() && !entry.restart_pending() {
+                results.push(json!({"session_key": key, "error": "Session is not running"}));
+                continue;
+            }
 
-                json!({"status": "stopped", "session_key": session_key})
+            let now = Instant::now();
+            entry.last_activity = now;
+            if let Some(extend) = extend {
+                let from = entry.keepalive_until.map_or(now, |until| until.max(now));
+                entry.keepalive_until = from.checked_add(extend).or(entry.keepalive_until);
+            }
+            if let Some(pin) = pin {
+                entry.pinned = pin;
             }
-            Err(e) => json!({"error": format!("Failed to stop server: {}", e)}),
+            let mut result = json!({"session_key": key});
+            add_idle_shutdown(&mut result, entry, idle_timeout);
+            results.push(result);
+        }
+        if pin.is_some() {
+            guard.persist();
+        }
+
+        match results.len() {
+            1 if !guard.groups.contains_key(&session_key) => results.remove(0),
+            _ => json!({"group_key": session_key, "members": results}),
         }
     }
 
@@ -256,16 +1330,29 @@ impl Manager {
             Ok(g) => g,
             Err(e) => return json!({"error": format!("Lock error: {}", e)}),
         };
+        let idle_timeout = guard.idle_timeout;
+        let ManagerInner { servers, groups, .. } = &mut *guard;
 
         if let Some(key) = session_key {
-            if let Some(entry) = guard.servers.get_mut(&key) {
-                entry.last_activity = Instant::now();
-                let mut result = json!({
-                    "session_key": key,
-                    "running": entry.is_running()
-                });
-                if entry.port != 0 {
-                    result["port"] = json!(entry.port);
+            if let Some(members) = groups.get(&key) {
+                let members: Vec<_> = members
+                    .iter()
+                    .map(|member| {
+                        let mut result = match servers.get_mut(&member.session_key) {
+                            Some(entry) => touch_status(&member.session_key, entry, idle_timeout),
+                            None => json!({"session_key": member.session_key, "running": false}),
+                        };
+                        result["name"] = json!(member.name);
+                        result
+                    })
+                    .collect();
+                return json!({"group_key": key, "members": members});
+            }
+
+            if let Some(entry) = servers.get_mut(&key) {
+                let mut result = touch_status(&key, entry, idle_timeout);
+                if let Some(group_key) = guard.group_of(&key) {
+                    result["group"] = json!(group_key);
                 }
                 return result;
             } else {
@@ -273,26 +1360,39 @@ impl Manager {
             }
         }
 
-        let sessions: Vec<_> = guard
-            .servers
+        let sessions: Vec<_> = servers
             .iter_mut()
-            .map(|(key, entry)| {
-                entry.last_activity = Instant::now();
-                let mut result = json!({
-                    "session_key": key,
-                    "running": entry.is_running()
-                });
-                if entry.port != 0 {
-                    result["port"] = json!(entry.port);
+            .map(|(key, entry)| (key.clone(), touch_status(key, entry, idle_timeout)))
+            .collect();
+        let sessions: Vec<_> = sessions
+            .into_iter()
+            .map(|(key, mut result)| {
+                if let Some(group_key) = guard.group_of(&key) {
+                    result["group"] = json!(group_key);
                 }
                 result
             })
             .collect();
 
-        json!({"sessions": sessions})
+        let mut result = json!({"sessions": sessions});
+        if !guard.groups.is_empty() {
+            let groups: Vec<_> = guard
+                .groups
+                .iter()
+                .map(|(group_key, members)| {
+                    let members: serde_json::Map<_, _> = members
+                        .iter()
+                        .map(|m| (m.name.clone(), json!(m.session_key)))
+                        .collect();
+                    json!({"group_key": group_key, "members": members})
+                })
+                .collect();
+            result["groups"] = json!(groups);
+        }
+        result
     }
 
-    pub fn tail(&self, session_key: String) -> serde_json::Value {
+    pub fn tail(&self, session_key: String, options: TailOptions) -> serde_json::Value {
         let mut guard = match self.inner.lock() {
             Ok(g) => g,
             Err(e) => return json!({"error": format!("Lock error: {}", e)}),
@@ -300,14 +1400,363 @@ impl Manager {
 
         if let Some(entry) = guard.servers.get_mut(&session_key) {
             entry.last_activity = Instant::now();
-            let (stdout, stderr) = entry.get_logs();
-            json!({
+            entry.poll_exit();
+            let exit = entry.exit_info().map(exit_status);
+            let query = |since: Option<u64>| LogQuery {
+                since,
+                limit: options.limit,
+                from_start: options.from_start,
+            };
+            let (stdout, stderr) = match entry.read_logs(
+                &query(options.since.map(|c| c.stdout)),
+                &query(options.since.map(|c| c.stderr)),
+            ) {
+                Some(logs) => logs,
+                None => return json!({"error": "Log buffer unavailable"}),
+            };
+
+            let origin = entry.started_at;
+            let render = |line: &LogLine, stream: Option<LogStream>, timestamps: Option<TimestampFormat>| {
+                let mut out = String::new();
+                if let Some(format) = timestamps {
+                    out.push_str(&format.format(line.at, origin));
+                    out.push(' ');
+                }
+                if let Some(stream) = stream {
+                    out.push_str(&format!("[{}] ", stream.as_str()));
+                }
+                out.push_str(&line.text);
+                out
+            };
+
+            let evicted = json!({"stdout": stdout.evicted, "stderr": stderr.evicted});
+            if options.combined {
+                let combined = log_buffer::merge_chronological(stdout, stderr, options.limit);
+                let text: String = combined
+                    .lines
+                    .iter()
+                    .map(|(stream, line)| {
+                        render(line, Some(*stream), options.timestamps.or(Some(TimestampFormat::Iso)))
+                    })
+                    .collect();
+                let mut result = json!({
+                    "session_key": session_key,
+                    "combined": text,
+                    "cursor": combined.cursor,
+                    "evicted": evicted,
+                    "truncated": combined.truncated
+                });
+                if let Some(exit) = exit {
+                    result["exit"] = exit;
+                }
+                return result;
+            }
+
+            let text = |lines: &[LogLine]| -> String { lines.iter().map(|l| render(l, None, options.timestamps)).collect() };
+            let mut result = json!({
                 "session_key": session_key,
-                "stdout": stdout,
-                "stderr": stderr
-            })
+                "stdout": text(&stdout.lines),
+                "stderr": text(&stderr.lines),
+                "cursor": LogCursor { stdout: stdout.cursor, stderr: stderr.cursor },
+                "evicted": evicted,
+                "truncated": {"stdout": stdout.truncated, "stderr": stderr.truncated}
+            });
+            if let Some(exit) = exit {
+                result["exit"] = exit;
+            }
+            result
         } else {
             json!({"error": "Session not found"})
         }
     }
+
+    pub async fn follow(
+        &self,
+        session_key: String,
+        timeout: Duration,
+        pattern: Option<Regex>,
+        stream: Option<LogStream>,
+        notify: Option<mpsc::UnboundedSender<LogEvent>>,
+    ) -> serde_json::Value {
+        let mut rx = {
+            let mut guard = match self.inner.lock() {
+                Ok(g) => g,
+                Err(e) => return json!({"error": format!("Lock error: {}", e)}),
+            };
+            match guard.servers.get_mut(&session_key) {
+                Some(entry) => {
+                    entry.last_activity = Instant::now();
+                    entry.subscribe_logs()
+                }
+                None => return json!({"error": "Session not found"}),
+            }
+        };
+
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+        let mut exit_poll = tokio::time::interval(FOLLOW_EXIT_POLL_INTERVAL);
+
+        let mut lines = Vec::new();
+        let mut skipped = 0u64;
+        let mut truncated = false;
+        let mut matched = false;
+        let mut exited = false;
+
+        let mut handle = |event: LogEvent| -> bool {
+            if stream.is_some_and(|s| s != event.stream) {
+                return false;
+            }
+            if let Some(tx) = &notify {
+                let _ = tx.send(event.clone());
+            }
+            let is_match = pattern.as_ref().is_some_and(|re| re.is_match(event.line.trim_end_matches('\n')));
+            if lines.len() < MAX_FOLLOW_LINES {
+                lines.push(json!({"stream": event.stream.as_str(), "line": event.line}));
+            } else {
+                truncated = true;
+            }
+            is_match
+        };
+
+        loop {
+            tokio::select! {
+                _ = &mut deadline => break,
+                _ = exit_poll.tick() => {
+                    if !self.poll_running(&session_key) {
+                        exited = true;
+                        // Pick up output the readers flushed just before the exit was seen
+                        while let Ok(event) = rx.try_recv() {
+                            if handle(event) {
+                                matched = true;
+                                break;
+                            }
+                        }
+                        break;
+                    }
+                }
+                event = rx.recv() => match event {
+                    Ok(event) => {
+                        if handle(event) {
+                            matched = true;
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => skipped += n,
+                    Err(broadcast::error::RecvError::Closed) => {
+                        exited = true;
+                        break;
+                    }
+                },
+            }
+        }
+
+        if let Ok(mut guard) = self.inner.lock() {
+            if let Some(entry) = guard.servers.get_mut(&session_key) {
+                entry.last_activity = Instant::now();
+            }
+        }
+
+        let mut result = json!({
+            "session_key": session_key,
+            "lines": lines,
+            "exited": exited,
+            "truncated": truncated,
+            "skipped": skipped
+        });
+        if pattern.is_some() {
+            result["matched"] = json!(matched);
+        }
+        result
+    }
+
+    pub fn search_logs(
+        &self,
+        session_key: Option<String>,
+        pattern: Regex,
+        stream: Option<LogStream>,
+        context: usize,
+        max_matches: usize,
+    ) -> serde_json::Value {
+        let mut guard = match self.inner.lock() {
+            Ok(g) => g,
+            Err(e) => return json!({"error": format!("Lock error: {}", e)}),
+        };
+
+        if let Some(key) = &session_key {
+            if !guard.servers.contains_key(key) {
+                return json!({"error": "Session not found"});
+            }
+        }
+
+        let numbered = |lines: Vec<(u64, String)>| -> Vec<serde_json::Value> {
+            lines
+                .into_iter()
+                .map(|(line, text)| json!({"line": line, "text": text}))
+                .collect()
+        };
+
+        let mut matches = Vec::new();
+        let mut truncated = false;
+        for (key, entry) in guard.servers.iter_mut() {
+            if session_key.as_ref().is_some_and(|k| k != key) {
+                continue;
+            }
+            entry.last_activity = Instant::now();
+
+            let (found, more) = entry.search_logs(&pattern, stream, context, max_matches - matches.len());
+            truncated |= more;
+            for (log_stream, m) in found {
+                matches.push(json!({
+                    "session_key": key,
+                    "stream": log_stream.as_str(),
+                    "line": m.line,
+                    "text": m.text,
+                    "before": numbered(m.before),
+                    "after": numbered(m.after)
+                }));
+            }
+        }
+
+        json!({"matches": matches, "truncated": truncated})
+    }
+
+    fn poll_running(&self, session_key: &str) -> bool {
+        let mut guard = match self.inner.lock() {
+            Ok(g) => g,
+            Err(_) => return false,
+        };
+        match guard.servers.get_mut(session_key) {
+            Some(entry) => {
+                entry.poll_exit();
+                entry.is_running()
+            }
+            None => false,
+        }
+    }
+
+    pub fn list_services(&self, cwd: Option<String>) -> serde_json::Value {
+        let config = match project_config::discover_from(cwd.as_deref()) {
+            Ok(c) => c,
+            Err(e) => return json!({"error": e.to_string()}),
+        };
+
+        let guard = match self.inner.lock() {
+            Ok(g) => g,
+            Err(e) => return json!({"error": format!("Lock error: {}", e)}),
+        };
+
+        let services: Vec<_> = config
+            .services
+            .iter()
+            .map(|(name, def)| {
+                let cwd = config.service_cwd(def).to_string_lossy().to_string();
+                let running: Vec<_> = guard
+                    .servers
+                    .iter()
+                    .filter(|(_, entry)| {
+                        entry.is_running()
+                            && entry.spec.service.as_deref() == Some(name.as_str())
+                            && entry.spec.cwd.as_deref() == Some(cwd.as_str())
+                    })
+                    .map(|(key, _)| key.clone())
+                    .collect();
+                json!({
+                    "name": name,
+                    "command": def.command,
+                    "cwd": cwd,
+                    "env": def.env.keys().collect::<Vec<_>>(),
+                    "env_file": def.env_file,
+                    "port": def.port,
+                    "ready": def.ready,
+                    "sessions": running
+                })
+            })
+            .collect();
+
+        json!({
+            "config": config.path.to_string_lossy(),
+            "services": services
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(name: &str, depends_on: &[&str]) -> GroupMemberSpec {
+        GroupMemberSpec {
+            name: name.to_string(),
+            spec: serde_json::from_value(json!({"command": "true", "cwd": null})).unwrap(),
+            readiness: None,
+            preferred_port: None,
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    fn names(members: &[GroupMemberSpec]) -> Result<Vec<&str>, String> {
+        topological_order(members).map(|order| {
+            order
+                .into_iter()
+                .map(|i| members[i].name.as_str())
+                .collect()
+        })
+    }
+
+    #[test]
+    fn orders_dependencies_first() {
+        let members = [
+            member("web", &["api"]),
+            member("api", &["db", "cache"]),
+            member("db", &[]),
+            member("cache", &[]),
+        ];
+        assert_eq!(names(&members).unwrap(), ["db", "cache", "api", "web"]);
+    }
+
+    #[test]
+    fn keeps_declaration_order_without_dependencies() {
+        let members = [member("a", &[]), member("b", &[]), member("c", &[])];
+        assert_eq!(names(&members).unwrap(), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn rejects_cycles() {
+        let members = [
+            member("db", &[]),
+            member("api", &["worker"]),
+            member("worker", &["api"]),
+        ];
+        assert_eq!(
+            names(&members).unwrap_err(),
+            "Dependency cycle between: api, worker"
+        );
+
+        let members = [member("api", &["api"])];
+        assert_eq!(
+            names(&members).unwrap_err(),
+            "Dependency cycle between: api"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_and_duplicate_members() {
+        let members = [member("api", &["db"])];
+        assert_eq!(
+            names(&members).unwrap_err(),
+            "'api' depends on unknown member 'db'"
+        );
+
+        let members = [member("api", &[]), member("api", &[])];
+        assert_eq!(
+            names(&members).unwrap_err(),
+            "Group member names must be unique"
+        );
+
+        let members = [member("api-1", &[]), member("api_1", &[])];
+        assert_eq!(
+            names(&members).unwrap_err(),
+            "'api-1' and 'api_1' would both be exported as API_1_PORT"
+        );
+    }
 }
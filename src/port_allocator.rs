@@ -1,53 +1,252 @@
-use std::collections::{HashSet, VecDeque};
-use std::net::TcpListener;
+use crate::state_file::unix_secs;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener};
+use std::time::{Duration, SystemTime};
+
+const PROBE_ADDRS: [IpAddr; 4] = [
+    IpAddr::V4(Ipv4Addr::LOCALHOST),
+    IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+    IpAddr::V6(Ipv6Addr::LOCALHOST),
+    IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+];
+
+// Remembered ports steer other projects away, so forget ones that are not coming back
+const STICKY_MAX_ENTRIES: usize = 100;
+const STICKY_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+struct StickyPort {
+    port: u16,
+    last_used: u64,
+}
 
 pub struct PortAllocator {
+    start_port: u16,
+    end_port: u16,
     next_port: u16,
+    excluded: HashSet<u16>,
     free_list: VecDeque<u16>,
     in_use: HashSet<u16>,
+    sticky: HashMap<String, StickyPort>,
 }
 
 impl PortAllocator {
-    pub fn new(start_port: u16) -> Self {
-        Self {
+    pub fn new(start_port: u16, end_port: u16, excluded: impl IntoIterator<Item = u16>) -> anyhow::Result<Self> {
+        if start_port == 0 || start_port > end_port {
+            anyhow::bail!("Invalid port range {}-{}", start_port, end_port);
+        }
+        Ok(Self {
+            start_port,
+            end_port,
             next_port: start_port,
+            excluded: excluded.into_iter().collect(),
             free_list: VecDeque::new(),
             in_use: HashSet::new(),
-        }
+            sticky: HashMap::new(),
+        })
     }
 
     pub fn allocate(&mut self) -> anyhow::Result<u16> {
+        // Leave ports remembered for other projects alone unless nothing else is free
+        let remembered: HashSet<u16> = self.sticky.values().map(|s| s.port).collect();
+        if let Some(port) = self.scan(&remembered).or_else(|| self.scan(&HashSet::new())) {
+            return Ok(port);
+        }
+
+        anyhow::bail!(
+            "No free ports in range {}-{} ({} allocated, {} excluded)",
+            self.start_port,
+            self.end_port,
+            self.in_use.len(),
+            self.excluded
+                .iter()
+                .filter(|p| (self.start_port..=self.end_port).contains(*p))
+                .count()
+        )
+    }
+
+    fn scan(&mut self, avoid: &HashSet<u16>) -> Option<u16> {
         // Try reusing from free list first
-        while let Some(port) = self.free_list.pop_front() {
-            if self.is_available(port) {
+        let mut idx = 0;
+        while idx < self.free_list.len() {
+            let port = self.free_list[idx];
+            if avoid.contains(&port) {
+                idx += 1;
+                continue;
+            }
+            self.free_list.remove(idx);
+            if port_free(port) {
                 self.in_use.insert(port);
-                return Ok(port);
+                return Some(port);
+            }
+        }
+
+        // Allocate sequentially, wrapping around to the start of the range once
+        let range_len = (self.end_port - self.start_port) as u32 + 1;
+        for _ in 0..range_len {
+            let port = self.next_port;
+            self.next_port = if port == self.end_port { self.start_port } else { port + 1 };
+
+            if !avoid.contains(&port) && self.try_reserve(port) {
+                return Some(port);
             }
         }
+        None
+    }
 
-        // Allocate new port sequentially
-        loop {
-            if self.next_port == u16::MAX {
-                anyhow::bail!("Port allocation overflow - no more ports available");
+    pub fn allocate_sticky(&mut self, key: &str, preferred: Option<u16>) -> anyhow::Result<(u16, Option<String>)> {
+        if preferred == Some(0) {
+            anyhow::bail!("Invalid preferred port 0");
+        }
+        if let Some(port) = preferred {
+            if self.try_reserve(port) {
+                self.remember(key, port, true);
+                return Ok((port, None));
             }
+        }
 
-            let port = self.next_port;
-            self.next_port += 1;
+        let port = match self.sticky.get(key).map(|s| s.port) {
+            Some(port) if Some(port) != preferred && self.try_reserve(port) => port,
+            _ => self.allocate()?,
+        };
+        self.remember(key, port, false);
 
-            if !self.in_use.contains(&port) && self.is_available(port) {
-                self.in_use.insert(port);
-                return Ok(port);
+        let warning = preferred.map(|p| format!("Preferred port {} is unavailable; using {} instead", p, port));
+        Ok((port, warning))
+    }
+
+    fn remember(&mut self, key: &str, port: u16, replace: bool) {
+        let last_used = unix_secs(SystemTime::now());
+        let entry = self
+            .sticky
+            .entry(key.to_string())
+            .or_insert(StickyPort { port, last_used });
+        if replace {
+            entry.port = port;
+        }
+        entry.last_used = last_used;
+        self.prune_sticky();
+    }
+
+    // Drops entries unused for STICKY_TTL, then the least recently used beyond STICKY_MAX_ENTRIES
+    fn prune_sticky(&mut self) {
+        let cutoff = unix_secs(SystemTime::now()).saturating_sub(STICKY_TTL.as_secs());
+        self.sticky.retain(|_, s| s.last_used >= cutoff);
+        if self.sticky.len() > STICKY_MAX_ENTRIES {
+            let mut by_age: Vec<(u64, String)> = self.sticky.iter().map(|(k, s)| (s.last_used, k.clone())).collect();
+            by_age.sort();
+            for (_, key) in by_age.into_iter().take(self.sticky.len() - STICKY_MAX_ENTRIES) {
+                self.sticky.remove(&key);
             }
         }
     }
 
+    pub fn sticky_ports(&self) -> (BTreeMap<String, u16>, BTreeMap<String, u64>) {
+        let ports = self.sticky.iter().map(|(k, s)| (k.clone(), s.port)).collect();
+        let last_used = self.sticky.iter().map(|(k, s)| (k.clone(), s.last_used)).collect();
+        (ports, last_used)
+    }
+
+    // State files from before last-use tracking count their entries as used now
+    pub fn restore_sticky(&mut self, ports: BTreeMap<String, u16>, last_used: &BTreeMap<String, u64>) {
+        let now = unix_secs(SystemTime::now());
+        for (key, port) in ports {
+            let last_used = last_used.get(&key).copied().unwrap_or(now);
+            self.sticky.insert(key, StickyPort { port, last_used });
+        }
+        self.prune_sticky();
+    }
+
+    fn try_reserve(&mut self, port: u16) -> bool {
+        if self.in_use.contains(&port) || self.excluded.contains(&port) || !port_free(port) {
+            return false;
+        }
+        self.reserve(port);
+        true
+    }
+
+    pub fn reserve(&mut self, port: u16) {
+        self.in_use.insert(port);
+        self.free_list.retain(|p| *p != port);
+    }
+
     pub fn free(&mut self, port: u16) {
         if self.in_use.remove(&port) {
             self.free_list.push_back(port);
         }
     }
+}
+
+pub fn port_free(port: u16) -> bool {
+    // Only an address conflict means the port is taken; hosts without IPv6 fail the v6 probes otherwise
+    PROBE_ADDRS.iter().all(|ip| match TcpListener::bind(SocketAddr::new(*ip, port)) {
+        Ok(_) => true,
+        Err(e) => e.kind() != ErrorKind::AddrInUse,
+    })
+}
 
-    fn is_available(&self, port: u16) -> bool {
-        TcpListener::bind(("127.0.0.1", port)).is_ok()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_preferred_port_zero() {
+        let mut allocator = PortAllocator::new(3010, 3020, []).unwrap();
+        assert!(allocator.allocate_sticky("app", Some(0)).is_err());
+        assert!(allocator.in_use.is_empty());
+    }
+
+    #[test]
+    fn scan_wraps_around_and_skips_excluded_ports() {
+        let mut allocator = PortAllocator::new(47310, 47313, [47311]).unwrap();
+        allocator.next_port = 47313;
+        assert_eq!(allocator.allocate().unwrap(), 47313);
+        assert_eq!(allocator.allocate().unwrap(), 47310);
+        assert_eq!(allocator.allocate().unwrap(), 47312);
+
+        let err = allocator.allocate().unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "No free ports in range 47310-47313 (3 allocated, 1 excluded)"
+        );
+
+        // Freed ports are handed out again before the range is rescanned
+        allocator.free(47310);
+        assert_eq!(allocator.allocate().unwrap(), 47310);
+    }
+
+    #[test]
+    fn scan_avoids_remembered_ports_until_the_range_is_exhausted() {
+        let mut allocator = PortAllocator::new(47320, 47321, []).unwrap();
+        allocator.remember("other", 47320, true);
+        assert_eq!(allocator.allocate().unwrap(), 47321);
+        assert_eq!(allocator.allocate().unwrap(), 47320);
+    }
+
+    #[test]
+    fn prunes_expired_and_least_recently_used_entries() {
+        let mut allocator = PortAllocator::new(3010, 3020, []).unwrap();
+        let now = unix_secs(SystemTime::now());
+        let mut ports = BTreeMap::new();
+        let mut last_used = BTreeMap::new();
+        for i in 0..STICKY_MAX_ENTRIES as u64 + 5 {
+            let key = format!("project-{}", i);
+            ports.insert(key.clone(), 4000 + i as u16);
+            last_used.insert(key, now - 1000 + i);
+        }
+        ports.insert("stale".to_string(), 5000);
+        last_used.insert("stale".to_string(), now - STICKY_TTL.as_secs() - 1);
+        ports.insert("legacy".to_string(), 5001);
+
+        allocator.restore_sticky(ports, &last_used);
+        let (kept, _) = allocator.sticky_ports();
+        assert_eq!(kept.len(), STICKY_MAX_ENTRIES);
+        assert!(!kept.contains_key("stale"));
+        // Entries without a recorded last use count as used now
+        assert!(kept.contains_key("legacy"));
+        for i in 0..6 {
+            assert!(!kept.contains_key(&format!("project-{}", i)));
+        }
+        assert!(kept.contains_key("project-6"));
     }
 }
@@ -0,0 +1,424 @@
+use serde::Serialize;
+use std::time::Instant;
+
+#[derive(Clone, Serialize)]
+pub struct ProcessSample {
+    pub pid: i32,
+    pub ppid: i32,
+    pub name: String,
+    pub rss_bytes: u64,
+    pub threads: u64,
+    pub fds: u64,
+    #[serde(skip)]
+    pub cpu_ticks: u64,
+}
+
+#[derive(Clone, Copy, Default, Serialize)]
+pub struct UsageTotals {
+    pub rss_bytes: u64,
+    pub cpu_percent: f64,
+    pub threads: u64,
+    pub fds: u64,
+    pub processes: usize,
+}
+
+impl UsageTotals {
+    fn max(self, other: UsageTotals) -> UsageTotals {
+        UsageTotals {
+            rss_bytes: self.rss_bytes.max(other.rss_bytes),
+            cpu_percent: self.cpu_percent.max(other.cpu_percent),
+            threads: self.threads.max(other.threads),
+            fds: self.fds.max(other.fds),
+            processes: self.processes.max(other.processes),
+        }
+    }
+}
+
+pub struct GroupUsage {
+    pub totals: UsageTotals,
+    pub processes: Vec<ProcessSample>,
+}
+
+#[derive(Default)]
+pub struct UsageTracker {
+    last_cpu: Option<(u64, Instant)>,
+    pub current: Option<GroupUsage>,
+    pub peak: UsageTotals,
+    pub listening_ports: Vec<u16>,
+}
+
+impl UsageTracker {
+    pub fn record(&mut self, processes: Vec<ProcessSample>, listening_ports: Vec<u16>) {
+        self.listening_ports = listening_ports;
+        if processes.is_empty() {
+            self.current = None;
+            return;
+        }
+
+        let now = Instant::now();
+        let ticks: u64 = processes.iter().map(|p| p.cpu_ticks).sum();
+        // CPU% is the tick delta since the previous sample; a fresh process group reads 0 once
+        let cpu_percent = match self.last_cpu {
+            Some((last_ticks, at)) if ticks >= last_ticks => {
+                let elapsed = now.duration_since(at).as_secs_f64();
+                let busy = (ticks - last_ticks) as f64 / clock_ticks_per_sec() as f64;
+                if elapsed > 0.0 { (busy / elapsed * 1000.0).round() / 10.0 } else { 0.0 }
+            }
+            _ => 0.0,
+        };
+        self.last_cpu = Some((ticks, now));
+
+        let totals = UsageTotals {
+            rss_bytes: processes.iter().map(|p| p.rss_bytes).sum(),
+            cpu_percent,
+            threads: processes.iter().map(|p| p.threads).sum(),
+            fds: processes.iter().map(|p| p.fds).sum(),
+            processes: processes.len(),
+        };
+        self.peak = self.peak.max(totals);
+        self.current = Some(GroupUsage { totals, processes });
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::ProcessSample;
+    use std::collections::{BTreeSet, HashMap, HashSet};
+    use std::fs;
+
+    pub fn clock_ticks_per_sec() -> u64 {
+        let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks > 0 { ticks as u64 } else { 100 }
+    }
+
+    fn page_size() -> u64 {
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 { size as u64 } else { 4096 }
+    }
+
+    fn process_sample(pid: i32) -> Option<ProcessSample> {
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        let name = stat[stat.find('(')? + 1..stat.rfind(')')?].to_string();
+        let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+        let field = |idx: usize| fields.get(idx).and_then(|f| f.parse::<i64>().ok()).unwrap_or(0);
+        let fds = fs::read_dir(format!("/proc/{}/fd", pid)).map(|d| d.count()).unwrap_or(0);
+
+        // Indices are relative to the state field (stat field 3): ppid, utime, stime, threads, rss
+        Some(ProcessSample {
+            pid,
+            ppid: field(1) as i32,
+            name,
+            rss_bytes: field(21).max(0) as u64 * page_size(),
+            threads: field(17).max(0) as u64,
+            fds: fds as u64,
+            cpu_ticks: (field(11) + field(12)).max(0) as u64,
+        })
+    }
+
+    pub fn processes(pids: &[i32]) -> Vec<ProcessSample> {
+        let mut processes: Vec<ProcessSample> = pids.iter().filter_map(|pid| process_sample(*pid)).collect();
+        processes.sort_by_key(|p| p.pid);
+        processes
+    }
+
+    // Fields after the parenthesised comm, which may itself contain spaces or parens
+    fn stat_fields(pid: i32) -> Option<Vec<String>> {
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        let rest = &stat[stat.rfind(')')? + 1..];
+        Some(rest.split_whitespace().map(str::to_string).collect())
+    }
+
+    fn all_pids() -> Vec<i32> {
+        let entries = match fs::read_dir("/proc") {
+            Ok(e) => e,
+            Err(_) => return Vec::new(),
+        };
+        entries
+            .filter_map(|e| e.ok()?.file_name().to_str()?.parse::<i32>().ok())
+            .collect()
+    }
+
+    pub fn group_pids(pgid: i32) -> Vec<i32> {
+        all_pids()
+            .into_iter()
+            .filter(|pid| {
+                stat_fields(*pid)
+                    .and_then(|f| f.get(2)?.parse::<i32>().ok())
+                    .is_some_and(|pgrp| pgrp == pgid)
+            })
+            .collect()
+    }
+
+    // Clock ticks after boot; together with the pid this identifies a process even once the pid is reused
+    pub fn start_time(pid: i32) -> Option<u64> {
+        stat_fields(pid)?.get(19)?.parse().ok()
+    }
+
+    // Zombies have already exited and only wait to be reaped by their parent
+    pub fn is_live(pid: i32) -> bool {
+        stat_fields(pid).is_some_and(|f| f.first().is_some_and(|state| state != "Z" && state != "X"))
+    }
+
+    // The roots plus everything below them, following parent links
+    pub fn descendants(roots: &[i32]) -> Vec<i32> {
+        let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+        for pid in all_pids() {
+            if let Some(ppid) = stat_fields(pid).and_then(|f| f.get(1)?.parse::<i32>().ok()) {
+                children.entry(ppid).or_default().push(pid);
+            }
+        }
+        let mut found: BTreeSet<i32> = roots.iter().copied().collect();
+        let mut queue: Vec<i32> = roots.to_vec();
+        while let Some(pid) = queue.pop() {
+            for child in children.get(&pid).into_iter().flatten() {
+                if found.insert(*child) {
+                    queue.push(*child);
+                }
+            }
+        }
+        found.into_iter().collect()
+    }
+
+    pub fn has_env(pid: i32, var: &str, value: &str) -> bool {
+        let needle = format!("{}={}", var, value);
+        fs::read(format!("/proc/{}/environ", pid))
+            .is_ok_and(|environ| environ.split(|b| *b == 0).any(|entry| entry == needle.as_bytes()))
+    }
+
+    fn env_value(pid: i32, var: &str) -> Option<String> {
+        let prefix = format!("{}=", var);
+        let environ = fs::read(format!("/proc/{}/environ", pid)).ok()?;
+        let entry = environ.split(|b| *b == 0).find_map(|entry| entry.strip_prefix(prefix.as_bytes()))?;
+        String::from_utf8(entry.to_vec()).ok()
+    }
+
+    struct TableEntry {
+        pgrp: i32,
+        live: bool,
+        tag: Option<String>,
+    }
+
+    // One pass over /proc with what it takes to find a session's processes, shared by every session in a sweep
+    #[derive(Default)]
+    pub struct ProcessTable {
+        procs: HashMap<i32, TableEntry>,
+        children: HashMap<i32, Vec<i32>>,
+    }
+
+    impl ProcessTable {
+        // Records each process's value of the env var `tag` along with its parent and group
+        pub fn snapshot(tag: &str) -> Self {
+            let mut table = Self::default();
+            for pid in all_pids() {
+                let Some(fields) = stat_fields(pid) else { continue };
+                let field = |idx: usize| fields.get(idx).and_then(|f| f.parse::<i32>().ok());
+                let (Some(ppid), Some(pgrp)) = (field(1), field(2)) else { continue };
+                let live = fields.first().is_some_and(|state| state != "Z" && state != "X");
+                table.children.entry(ppid).or_default().push(pid);
+                table.procs.insert(pid, TableEntry { pgrp, live, tag: env_value(pid, tag) });
+            }
+            table
+        }
+
+        // The live members of group `pgid`, the processes tagged `value`, and everything below them
+        pub fn tree(&self, pgid: i32, value: &str) -> Vec<i32> {
+            let mut found: BTreeSet<i32> = self
+                .procs
+                .iter()
+                .filter(|(_, p)| p.pgrp == pgid || p.tag.as_deref() == Some(value))
+                .map(|(pid, _)| *pid)
+                .collect();
+            let mut queue: Vec<i32> = found.iter().copied().collect();
+            while let Some(pid) = queue.pop() {
+                for child in self.children.get(&pid).into_iter().flatten() {
+                    if found.insert(*child) {
+                        queue.push(*child);
+                    }
+                }
+            }
+            found
+                .into_iter()
+                .filter(|pid| self.procs.get(pid).is_some_and(|p| p.live))
+                .collect()
+        }
+    }
+
+    // Processes whose environment contains VAR=value, which survives setsid and double forks
+    pub fn tagged_pids(var: &str, value: &str) -> Vec<i32> {
+        all_pids().into_iter().filter(|pid| has_env(*pid, var, value)).collect()
+    }
+
+    fn socket_inodes(pid: i32) -> HashSet<u64> {
+        let entries = match fs::read_dir(format!("/proc/{}/fd", pid)) {
+            Ok(e) => e,
+            Err(_) => return HashSet::new(),
+        };
+        entries
+            .filter_map(|e| {
+                let target = fs::read_link(e.ok()?.path()).ok()?;
+                let target = target.to_str()?;
+                target.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
+            })
+            .collect()
+    }
+
+    const TCP_LISTEN: &str = "0A";
+
+    // (port, socket inode) of every listening socket in a /proc/net/tcp table
+    fn listen_sockets(path: &str) -> Vec<(u16, u64)> {
+        let table = match fs::read_to_string(path) {
+            Ok(t) => t,
+            Err(_) => return Vec::new(),
+        };
+        table
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                if fields.len() < 10 || fields[3] != TCP_LISTEN {
+                    return None;
+                }
+                let port = u16::from_str_radix(fields[1].rsplit(':').next()?, 16).ok()?;
+                Some((port, fields[9].parse().ok()?))
+            })
+            .collect()
+    }
+
+    fn listening_in_table(path: &str, inodes: &HashSet<u64>, ports: &mut BTreeSet<u16>) {
+        for (port, inode) in listen_sockets(path) {
+            if inodes.contains(&inode) {
+                ports.insert(port);
+            }
+        }
+    }
+
+    pub fn port_listeners(port: u16) -> Vec<i32> {
+        let inodes: HashSet<u64> = ["/proc/net/tcp", "/proc/net/tcp6"]
+            .iter()
+            .flat_map(|path| listen_sockets(path))
+            .filter(|(p, _)| *p == port)
+            .map(|(_, inode)| inode)
+            .collect();
+        if inodes.is_empty() {
+            return Vec::new();
+        }
+        all_pids()
+            .into_iter()
+            .filter(|pid| !socket_inodes(*pid).is_disjoint(&inodes))
+            .collect()
+    }
+
+    pub fn listening_ports(pids: &[i32]) -> Vec<u16> {
+        let inodes: HashSet<u64> = pids.iter().flat_map(|pid| socket_inodes(*pid)).collect();
+        let mut ports = BTreeSet::new();
+        if let Some(pid) = pids.first().filter(|_| !inodes.is_empty()) {
+            listening_in_table(&format!("/proc/{}/net/tcp", pid), &inodes, &mut ports);
+            listening_in_table(&format!("/proc/{}/net/tcp6", pid), &inodes, &mut ports);
+        }
+        ports.into_iter().collect()
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::*;
+
+#[cfg(not(target_os = "linux"))]
+pub fn group_pids(_pgid: i32) -> Vec<i32> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn start_time(_pid: i32) -> Option<u64> {
+    None
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn is_live(pid: i32) -> bool {
+    unsafe { libc::kill(pid, 0) == 0 }
+}
+
+#[cfg(not(unix))]
+pub fn is_live(_pid: i32) -> bool {
+    false
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn descendants(roots: &[i32]) -> Vec<i32> {
+    roots.to_vec()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn has_env(_pid: i32, _var: &str, _value: &str) -> bool {
+    false
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn tagged_pids(_var: &str, _value: &str) -> Vec<i32> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "linux"))]
+#[derive(Default)]
+pub struct ProcessTable;
+
+#[cfg(not(target_os = "linux"))]
+impl ProcessTable {
+    pub fn snapshot(_tag: &str) -> Self {
+        Self
+    }
+
+    pub fn tree(&self, _pgid: i32, _value: &str) -> Vec<i32> {
+        Vec::new()
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn port_listeners(_port: u16) -> Vec<i32> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn listening_ports(_pids: &[i32]) -> Vec<u16> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn processes(_pids: &[i32]) -> Vec<ProcessSample> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn clock_ticks_per_sec() -> u64 {
+    100
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_table_finds_tagged_processes_and_their_descendants() {
+        use std::os::unix::process::CommandExt;
+
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "sleep 30 & wait"])
+            .env("DEV_MANAGER_TEST_TAG", "tagged")
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let pid = child.id() as i32;
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        let table = ProcessTable::snapshot("DEV_MANAGER_TEST_TAG");
+        let by_tag = table.tree(i32::MAX, "tagged");
+        assert!(by_tag.contains(&pid));
+        assert_eq!(by_tag.len(), 2, "sh and its sleep: {:?}", by_tag);
+        assert_eq!(table.tree(pid, "other"), by_tag);
+        assert!(table.tree(i32::MAX, "other").is_empty());
+
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+        child.wait().unwrap();
+    }
+}
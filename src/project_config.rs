@@ -0,0 +1,114 @@
+use crate::server_entry::{IdleTimeout, RestartPolicy};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_FILE: &str = ".dev-manager.toml";
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct ReadyDef {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ServiceDef {
+    pub command: String,
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    pub env_file: Option<String>,
+    #[serde(default)]
+    pub clean_env: bool,
+    #[serde(default)]
+    pub ready: ReadyDef,
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub ports: Vec<String>,
+    #[serde(default)]
+    pub port_env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub restart: RestartPolicy,
+    pub max_restarts: Option<u32>,
+    #[serde(default)]
+    pub watch: Vec<String>,
+    pub memory_max: Option<String>,
+    pub cpu_max: Option<f64>,
+    pub pids_max: Option<u64>,
+    pub stop_signal: Option<String>,
+    pub stop_timeout: Option<u64>,
+    pub pre_stop: Option<String>,
+    pub idle_timeout: Option<IdleTimeout>,
+}
+
+#[derive(Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    services: BTreeMap<String, ServiceDef>,
+}
+
+pub struct ProjectConfig {
+    pub path: PathBuf,
+    pub services: BTreeMap<String, ServiceDef>,
+}
+
+impl ProjectConfig {
+    pub fn discover(start_dir: &Path) -> anyhow::Result<Option<Self>> {
+        for dir in start_dir.ancestors() {
+            let path = dir.join(CONFIG_FILE);
+            if path.is_file() {
+                let contents = std::fs::read_to_string(&path)?;
+                let file: ConfigFile = toml::from_str(&contents)
+                    .map_err(|e| anyhow::anyhow!("Invalid {}: {}", path.display(), e))?;
+                return Ok(Some(Self {
+                    path,
+                    services: file.services,
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn root(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("/"))
+    }
+
+    pub fn service_cwd(&self, def: &ServiceDef) -> PathBuf {
+        match &def.cwd {
+            Some(cwd) => self.root().join(cwd),
+            None => self.root().to_path_buf(),
+        }
+    }
+
+    pub fn service(&self, name: &str) -> anyhow::Result<&ServiceDef> {
+        self.services.get(name).ok_or_else(|| {
+            let known: Vec<_> = self.services.keys().map(String::as_str).collect();
+            anyhow::anyhow!(
+                "Service '{}' not defined in {} (available: {})",
+                name,
+                self.path.display(),
+                known.join(", ")
+            )
+        })
+    }
+}
+
+pub fn discover_from(cwd: Option<&str>) -> anyhow::Result<ProjectConfig> {
+    let start_dir = match cwd {
+        Some(cwd) => PathBuf::from(cwd),
+        None => std::env::current_dir()?,
+    };
+    ProjectConfig::discover(&start_dir)?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "No {} found in {} or its parent directories",
+            CONFIG_FILE,
+            start_dir.display()
+        )
+    })
+}
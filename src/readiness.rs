@@ -0,0 +1,111 @@
+use regex::Regex;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout, Duration};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+const MAX_RESPONSE_HEAD: usize = 1024;
+const DEFAULT_READY_TIMEOUT_SECS: u64 = 30;
+
+pub enum ReadyCheck {
+    Tcp,
+    Http(String),
+    LogPattern(Regex),
+}
+
+pub struct Readiness {
+    pub check: ReadyCheck,
+    pub timeout: Duration,
+}
+
+pub enum ReadyOutcome {
+    Ready(Duration),
+    Exited,
+    TimedOut,
+}
+
+fn loopback_addrs(port: u16) -> [SocketAddr; 2] {
+    [
+        SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+        SocketAddr::from((Ipv6Addr::LOCALHOST, port)),
+    ]
+}
+
+async fn connect(port: u16) -> Option<TcpStream> {
+    for addr in loopback_addrs(port) {
+        if let Ok(Ok(stream)) = timeout(CONNECT_TIMEOUT, TcpStream::connect(addr)).await {
+            return Some(stream);
+        }
+    }
+    None
+}
+
+pub async fn tcp_ready(port: u16) -> bool {
+    connect(port).await.is_some()
+}
+
+pub async fn http_ready(port: u16, path: &str) -> bool {
+    let mut stream = match connect(port).await {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    };
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: localhost:{}\r\nConnection: close\r\n\r\n",
+        path, port
+    );
+    if stream.write_all(request.as_bytes()).await.is_err() {
+        return false;
+    }
+
+    let mut head = Vec::new();
+    let mut buf = [0u8; 256];
+    while !head.contains(&b'\n') && head.len() < MAX_RESPONSE_HEAD {
+        match timeout(CONNECT_TIMEOUT, stream.read(&mut buf)).await {
+            Ok(Ok(n)) if n > 0 => head.extend_from_slice(&buf[..n]),
+            _ => break,
+        }
+    }
+
+    let status_line = String::from_utf8_lossy(&head);
+    status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .is_some_and(|code| (200..300).contains(&code))
+}
+
+impl Readiness {
+    pub fn from_params(
+        wait: Option<bool>,
+        http_path: Option<String>,
+        log_pattern: Option<String>,
+        timeout_secs: Option<u64>,
+    ) -> anyhow::Result<Option<Self>> {
+        if !wait.unwrap_or(true) {
+            return Ok(None);
+        }
+
+        let check = match (http_path, log_pattern) {
+            (Some(_), Some(_)) => {
+                anyhow::bail!("ready_path and ready_pattern cannot be combined")
+            }
+            (Some(path), None) => ReadyCheck::Http(path),
+            (None, Some(pattern)) => ReadyCheck::LogPattern(
+                Regex::new(&pattern).map_err(|e| anyhow::anyhow!("Invalid ready_pattern: {}", e))?,
+            ),
+            (None, None) => ReadyCheck::Tcp,
+        };
+
+        Ok(Some(Self {
+            check,
+            timeout: Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_READY_TIMEOUT_SECS)),
+        }))
+    }
+}
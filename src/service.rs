@@ -1,25 +1,118 @@
-use crate::manager::Manager;
+use crate::cgroup::ResourceLimits;
+use crate::log_buffer::{LogCursor, LogEvent, LogStream, TimestampFormat};
+use crate::manager::{GroupMemberSpec, Manager, TailOptions, TopSort};
+use crate::project_config::{self, ReadyDef};
+use crate::readiness::Readiness;
+use crate::server_entry::{
+    self, IdleTimeout, LaunchSpec, RestartPolicy, StopOptions, MAX_IDLE_TIMEOUT_SECS, MAX_STOP_TIMEOUT_SECS,
+};
+use crate::watcher;
+use regex::{Regex, RegexBuilder};
 use rmcp::handler::server::tool::ToolRouter;
 use rmcp::handler::server::wrapper::Parameters;
 use rmcp::model::{
     CallToolResult, Content, Implementation, InitializeRequestParam, InitializeResult,
-    ProtocolVersion, ServerCapabilities, ServerInfo,
+    LoggingLevel, LoggingMessageNotificationParam, ProgressNotificationParam, ProtocolVersion,
+    ServerCapabilities, ServerInfo,
 };
 use rmcp::service::RequestContext;
 use rmcp::{tool, tool_handler, tool_router, ErrorData, RoleServer, ServerHandler};
 use schemars::JsonSchema;
 use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+const DEFAULT_FOLLOW_SECS: u64 = 30;
+const MAX_FOLLOW_SECS: u64 = 300;
+const DEFAULT_SEARCH_CONTEXT: usize = 2;
+const MAX_SEARCH_CONTEXT: usize = 20;
+const DEFAULT_MAX_MATCHES: usize = 50;
 
 #[derive(Deserialize, JsonSchema, Clone)]
 struct StartRequest {
-    command: String,
+    command: Option<String>,
+    service: Option<String>,
+    cwd: Option<String>,
+    wait_ready: Option<bool>,
+    ready_path: Option<String>,
+    ready_pattern: Option<String>,
+    ready_timeout: Option<u64>,
+    reuse: Option<bool>,
+    preferred_port: Option<u16>,
+    ports: Option<Vec<String>>,
+    port_env: Option<BTreeMap<String, String>>,
+    restart: Option<RestartPolicy>,
+    max_restarts: Option<u32>,
+    watch: Option<Vec<String>>,
+    env: Option<BTreeMap<String, String>>,
+    env_file: Option<String>,
+    clean_env: Option<bool>,
+    memory_max: Option<String>,
+    cpu_max: Option<f64>,
+    pids_max: Option<u64>,
+    stop_signal: Option<String>,
+    stop_timeout: Option<u64>,
+    pre_stop: Option<String>,
+    idle_timeout: Option<IdleTimeout>,
+}
+
+#[derive(Deserialize, JsonSchema, Clone)]
+struct GroupMemberRequest {
+    name: String,
+    command: Option<String>,
+    service: Option<String>,
     cwd: Option<String>,
+    depends_on: Option<Vec<String>>,
+    wait_ready: Option<bool>,
+    ready_path: Option<String>,
+    ready_pattern: Option<String>,
+    ready_timeout: Option<u64>,
+}
+
+#[derive(Deserialize, JsonSchema, Clone)]
+struct StartGroupRequest {
+    members: Vec<GroupMemberRequest>,
+    cwd: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema, Clone)]
+struct ListServicesRequest {
+    cwd: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema, Clone)]
+struct RestartRequest {
+    session_key: String,
+    clear_logs: Option<bool>,
+    wait_ready: Option<bool>,
+    ready_path: Option<String>,
+    ready_pattern: Option<String>,
+    ready_timeout: Option<u64>,
+}
+
+#[derive(Deserialize, JsonSchema, Clone)]
+struct TopRequest {
+    sort: Option<TopSort>,
+    limit: Option<usize>,
 }
 
 #[derive(Deserialize, JsonSchema, Clone)]
 struct StopRequest {
     session_key: String,
+    signal: Option<String>,
+    timeout: Option<u64>,
+    pre_stop: Option<String>,
+    force: Option<bool>,
+}
+
+#[derive(Deserialize, JsonSchema, Clone)]
+struct KeepaliveRequest {
+    session_key: String,
+    extend_secs: Option<u64>,
+    pin: Option<bool>,
 }
 
 #[derive(Deserialize, JsonSchema, Clone)]
@@ -30,6 +123,29 @@ struct StatusRequest {
 #[derive(Deserialize, JsonSchema, Clone)]
 struct TailRequest {
     session_key: String,
+    since: Option<LogCursor>,
+    limit: Option<usize>,
+    from_start: Option<bool>,
+    combined: Option<bool>,
+    timestamps: Option<TimestampFormat>,
+}
+
+#[derive(Deserialize, JsonSchema, Clone)]
+struct FollowRequest {
+    session_key: String,
+    timeout: Option<u64>,
+    pattern: Option<String>,
+    stream: Option<LogStream>,
+}
+
+#[derive(Deserialize, JsonSchema, Clone)]
+struct SearchLogsRequest {
+    pattern: String,
+    session_key: Option<String>,
+    stream: Option<LogStream>,
+    context: Option<usize>,
+    max_matches: Option<usize>,
+    ignore_case: Option<bool>,
 }
 
 #[derive(Clone)]
@@ -47,29 +163,383 @@ impl DevManagerService {
     }
 }
 
+fn client_name(context: &RequestContext<RoleServer>) -> Option<String> {
+    let info = &context.peer.peer_info()?.client_info;
+    Some(format!("{} {}", info.name, info.version))
+}
+
+impl GroupMemberRequest {
+    fn resolve(self, group_cwd: Option<String>) -> anyhow::Result<GroupMemberSpec> {
+        let start = StartRequest {
+            command: self.command,
+            service: self.service,
+            cwd: self.cwd.or(group_cwd),
+            wait_ready: self.wait_ready,
+            ready_path: self.ready_path,
+            ready_pattern: self.ready_pattern,
+            ready_timeout: self.ready_timeout,
+            reuse: None,
+            preferred_port: None,
+            ports: None,
+            port_env: None,
+            restart: None,
+            max_restarts: None,
+            watch: None,
+            env: None,
+            env_file: None,
+            clean_env: None,
+            memory_max: None,
+            cpu_max: None,
+            pids_max: None,
+            stop_signal: None,
+            stop_timeout: None,
+            pre_stop: None,
+            idle_timeout: None,
+        };
+        let (spec, readiness, preferred_port) = start
+            .resolve()
+            .map_err(|e| anyhow::anyhow!("{}: {}", self.name, e))?;
+        Ok(GroupMemberSpec {
+            name: self.name,
+            spec,
+            readiness,
+            preferred_port,
+            depends_on: self.depends_on.unwrap_or_default(),
+        })
+    }
+}
+
+impl StartRequest {
+    fn resolve(self) -> anyhow::Result<(LaunchSpec, Option<Readiness>, Option<u16>)> {
+        let (spec, ready, preferred_port) = match (self.command, self.service) {
+            (Some(_), Some(_)) => anyhow::bail!("Specify either command or service, not both"),
+            (None, None) => anyhow::bail!("Either command or service is required"),
+            (Some(command), None) => {
+                let spec = LaunchSpec {
+                    command,
+                    cwd: self.cwd,
+                    env: BTreeMap::new(),
+                    env_file: None,
+                    clean_env: false,
+                    service: None,
+                    ports: Vec::new(),
+                    port_env: BTreeMap::new(),
+                    restart: RestartPolicy::Never,
+                    max_restarts: None,
+                    watch: Vec::new(),
+                    limits: ResourceLimits::default(),
+                    stop_signal: None,
+                    stop_timeout: None,
+                    pre_stop: None,
+                    idle_timeout: None,
+                };
+                (spec, ReadyDef::default(), None)
+            }
+            (None, Some(name)) => {
+                let config = project_config::discover_from(self.cwd.as_deref())?;
+                let def = config.service(&name)?;
+                let spec = LaunchSpec {
+                    command: def.command.clone(),
+                    cwd: Some(config.service_cwd(def).to_string_lossy().to_string()),
+                    env: def.env.clone(),
+                    env_file: def.env_file.clone(),
+                    clean_env: def.clean_env,
+                    service: Some(name),
+                    ports: def.ports.clone(),
+                    port_env: def.port_env.clone(),
+                    restart: def.restart,
+                    max_restarts: def.max_restarts,
+                    watch: def.watch.clone(),
+                    limits: ResourceLimits {
+                        memory_max: def.memory_max.clone(),
+                        cpu_max: def.cpu_max,
+                        pids_max: def.pids_max,
+                    },
+                    stop_signal: def.stop_signal.clone(),
+                    stop_timeout: def.stop_timeout,
+                    pre_stop: def.pre_stop.clone(),
+                    idle_timeout: def.idle_timeout,
+                };
+                (spec, def.ready.clone(), def.port)
+            }
+        };
+
+        let preferred_port = self.preferred_port.or(preferred_port);
+        if preferred_port == Some(0) {
+            anyhow::bail!("preferred_port must be between 1 and 65535");
+        }
+        let mut spec = spec;
+        if let Some(ports) = self.ports {
+            spec.ports = ports;
+        }
+        if let Some(port_env) = self.port_env {
+            spec.port_env = port_env;
+        }
+        if let Some(restart) = self.restart {
+            spec.restart = restart;
+        }
+        if self.max_restarts.is_some() {
+            spec.max_restarts = self.max_restarts;
+        }
+        if let Some(env) = self.env {
+            spec.env.extend(env);
+        }
+        if self.env_file.is_some() {
+            spec.env_file = self.env_file;
+        }
+        if let Some(clean_env) = self.clean_env {
+            spec.clean_env = clean_env;
+        }
+        if let Some(watch) = self.watch {
+            spec.watch = watch;
+        }
+        if self.memory_max.is_some() {
+            spec.limits.memory_max = self.memory_max;
+        }
+        if self.cpu_max.is_some() {
+            spec.limits.cpu_max = self.cpu_max;
+        }
+        if self.pids_max.is_some() {
+            spec.limits.pids_max = self.pids_max;
+        }
+        if self.stop_signal.is_some() {
+            spec.stop_signal = self.stop_signal;
+        }
+        if self.stop_timeout.is_some() {
+            spec.stop_timeout = self.stop_timeout;
+        }
+        if self.pre_stop.is_some() {
+            spec.pre_stop = self.pre_stop;
+        }
+        if self.idle_timeout.is_some() {
+            spec.idle_timeout = self.idle_timeout;
+        }
+        match spec.idle_timeout {
+            Some(IdleTimeout::Seconds(0)) => {
+                anyhow::bail!("idle_timeout must be at least 1 second; use \"none\" to disable it")
+            }
+            Some(IdleTimeout::Seconds(secs)) if secs > MAX_IDLE_TIMEOUT_SECS => anyhow::bail!(
+                "idle_timeout must be at most {} seconds (30 days); use \"none\" to disable it",
+                MAX_IDLE_TIMEOUT_SECS
+            ),
+            _ => {}
+        }
+        if let Some(signal) = &spec.stop_signal {
+            server_entry::signal_number(signal)?;
+        }
+        if spec.stop_timeout.is_some_and(|secs| secs > MAX_STOP_TIMEOUT_SECS) {
+            anyhow::bail!("stop_timeout must be at most {} seconds", MAX_STOP_TIMEOUT_SECS);
+        }
+        watcher::glob_set(&spec.watch)?;
+        spec.validate_ports()?;
+        spec.limits.validate()?;
+
+        let readiness = Readiness::from_params(
+            self.wait_ready.or(ready.wait),
+            self.ready_path.or(ready.path),
+            self.ready_pattern.or(ready.pattern),
+            self.ready_timeout.or(ready.timeout),
+        )?;
+        Ok((spec, readiness, preferred_port))
+    }
+}
+
 #[tool_router]
 impl DevManagerService {
-    #[tool(description = "Start a development server. Returns auto-generated session key, port number and status.")]
-    async fn start(&self, Parameters(req): Parameters<StartRequest>) -> Result<CallToolResult, ErrorData> {
-        let result = self.manager.start(req.command, req.cwd).await;
+    #[tool(description = "Start a development server from a shell `command`, or from a named `service` defined in the project's .dev-manager.toml (see list_services). Returns auto-generated session key, port number and status. If a session with the same command, cwd and env is already running, its key and port are returned with `reused: true` unless `reuse` is false. The same project gets the same port across restarts when it is free; `preferred_port` requests a specific port and falls back with a `warning` if it is taken. Extra named ports can be requested with `ports` (exported as <NAME>_PORT) and additional env var names mapped with `port_env` (use \"main\" for the primary PORT). `restart` (never, on-failure, always) restarts the server with exponential backoff when it exits on its own, up to `max_restarts` times (default 5), keeping its session key and ports. Extra environment variables can be passed with `env`, loaded from a dotenv `env_file` (relative to cwd), and `clean_env` starts from a minimal environment instead of the daemon's. `watch` globs (relative to cwd, e.g. \"src/**/*.js\") restart the server in place whenever a matching file changes. When the daemon runs with --cgroup-root, each session gets its own cgroup v2 and can be limited with `memory_max` (e.g. \"512M\"), `cpu_max` (number of CPUs, e.g. 1.5) and `pids_max`; an out-of-memory kill is reported as exit reason oom_killed. `stop_signal` (e.g. \"SIGINT\"), `stop_timeout` (seconds before SIGKILL, default 5) and a `pre_stop` command (run in the session's cwd and env before signalling) control how the server is stopped. `idle_timeout` overrides the daemon's idle timeout for this session in seconds, or \"none\" to never stop it for being idle. By default waits until the server accepts TCP connections on its port (or ready_path returns 2xx, or a log line matches ready_pattern) for up to ready_timeout seconds; set wait_ready to false to return immediately.")]
+    async fn start(
+        &self,
+        Parameters(req): Parameters<StartRequest>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let reuse = req.reuse.unwrap_or(true);
+        let (spec, readiness, preferred_port) = match req.resolve() {
+            Ok(r) => r,
+            Err(e) => {
+                let result = json!({"error": e.to_string()});
+                return Ok(CallToolResult::success(vec![Content::text(result.to_string())]));
+            }
+        };
+        let result = self
+            .manager
+            .start(spec, readiness, preferred_port, reuse, client_name(&context))
+            .await;
+        Ok(CallToolResult::success(vec![Content::text(result.to_string())]))
+    }
+
+    #[tool(description = "Start several dev servers as a group. Each member gets its own port plus <NAME>_PORT env vars for every member's port, and members start in `depends_on` order, each waiting for readiness before its dependents start. Returns a group key usable with stop (which tears down in reverse order) and status.")]
+    async fn start_group(
+        &self,
+        Parameters(req): Parameters<StartGroupRequest>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let members: anyhow::Result<Vec<_>> = req
+            .members
+            .into_iter()
+            .map(|member| member.resolve(req.cwd.clone()))
+            .collect();
+        let result = match members {
+            Ok(members) => self.manager.start_group(members, client_name(&context)).await,
+            Err(e) => json!({"error": e.to_string()}),
+        };
+        Ok(CallToolResult::success(vec![Content::text(result.to_string())]))
+    }
+
+    #[tool(description = "List the named services defined in the nearest .dev-manager.toml above cwd, with their commands, working directories, preferred ports, readiness checks and running sessions.")]
+    async fn list_services(&self, Parameters(req): Parameters<ListServicesRequest>) -> Result<CallToolResult, ErrorData> {
+        let result = self.manager.list_services(req.cwd);
         Ok(CallToolResult::success(vec![Content::text(result.to_string())]))
     }
 
-    #[tool(description = "Stop a running development server session.")]
+    #[tool(description = "Stop a running development server session, or every member of a group in reverse start order. Runs the session's pre_stop command, sends its stop signal (SIGTERM by default) to the whole process tree and escalates to SIGKILL after its stop timeout (5s by default). `signal`, `timeout` (seconds) and `pre_stop` override the session's settings for this call (an empty pre_stop skips it); `force` skips pre_stop and sends SIGKILL straight away. Reports `survivors` and `ports_in_use` if anything outlives the stop.")]
     async fn stop(&self, Parameters(req): Parameters<StopRequest>) -> Result<CallToolResult, ErrorData> {
-        let result = self.manager.stop(req.session_key).await;
+        if let Some(Err(e)) = req.signal.as_deref().map(server_entry::signal_number) {
+            let result = json!({"error": e.to_string()});
+            return Ok(CallToolResult::success(vec![Content::text(result.to_string())]));
+        }
+        if req.timeout.is_some_and(|secs| secs > MAX_STOP_TIMEOUT_SECS) {
+            let result = json!({"error": format!("timeout must be at most {} seconds", MAX_STOP_TIMEOUT_SECS)});
+            return Ok(CallToolResult::success(vec![Content::text(result.to_string())]));
+        }
+        let options = StopOptions {
+            signal: req.signal,
+            timeout: req.timeout.map(Duration::from_secs),
+            pre_stop: req.pre_stop,
+            force: req.force.unwrap_or(false),
+        };
+        let result = self.manager.stop(req.session_key, options).await;
+        Ok(CallToolResult::success(vec![Content::text(result.to_string())]))
+    }
+
+    #[tool(description = "Restart a session in place: stops its process group and re-runs the original command, cwd and env under the same session key and port. A group key restarts every member in start order. Set `clear_logs` to drop the captured output first (cursors stay valid). Waits for readiness like start unless wait_ready is false.")]
+    async fn restart(&self, Parameters(req): Parameters<RestartRequest>) -> Result<CallToolResult, ErrorData> {
+        let readiness = match Readiness::from_params(req.wait_ready, req.ready_path, req.ready_pattern, req.ready_timeout) {
+            Ok(r) => r,
+            Err(e) => {
+                let result = json!({"error": e.to_string()});
+                return Ok(CallToolResult::success(vec![Content::text(result.to_string())]));
+            }
+        };
+        let result = self
+            .manager
+            .restart(req.session_key, req.clear_logs.unwrap_or(false), readiness)
+            .await;
         Ok(CallToolResult::success(vec![Content::text(result.to_string())]))
     }
 
-    #[tool(description = "Get status of one or all development server sessions.")]
+    #[tool(description = "Get status of one or all development server sessions, or of the members of a group.")]
     async fn status(&self, Parameters(req): Parameters<StatusRequest>) -> Result<CallToolResult, ErrorData> {
         let result = self.manager.status(req.session_key);
         Ok(CallToolResult::success(vec![Content::text(result.to_string())]))
     }
 
-    #[tool(description = "Get stdout/stderr logs for a development server session.")]
+    #[tool(description = "Keep a session (or every member of a group) from being stopped for idleness. Resets its idle timer like any other tool call; `extend_secs` additionally keeps it alive for at least that many seconds from now, and `pin: true` disables idle shutdown until `pin: false`. Returns when the session will be stopped (`idle_shutdown_in_secs` is null while pinned).")]
+    async fn keepalive(&self, Parameters(req): Parameters<KeepaliveRequest>) -> Result<CallToolResult, ErrorData> {
+        if req.extend_secs.is_some_and(|secs| secs > MAX_IDLE_TIMEOUT_SECS) {
+            let result = json!({"error": format!(
+                "extend_secs must be at most {} seconds (30 days); use pin to disable idle shutdown",
+                MAX_IDLE_TIMEOUT_SECS
+            )});
+            return Ok(CallToolResult::success(vec![Content::text(result.to_string())]));
+        }
+        let extend = req.extend_secs.map(Duration::from_secs);
+        let result = self.manager.keepalive(req.session_key, extend, req.pin);
+        Ok(CallToolResult::success(vec![Content::text(result.to_string())]))
+    }
+
+    #[tool(description = "Rank running sessions by resource usage of their whole process group: RSS, CPU%, threads, open fds and process count, with peaks. `sort` is \"memory\" (default) or \"cpu\"; `limit` caps the number of sessions returned. Usage is sampled every second on Linux.")]
+    async fn top(&self, Parameters(req): Parameters<TopRequest>) -> Result<CallToolResult, ErrorData> {
+        let result = self.manager.top(req.sort.unwrap_or_default(), req.limit);
+        Ok(CallToolResult::success(vec![Content::text(result.to_string())]))
+    }
+
+    #[tool(description = "Get stdout/stderr logs for a development server session. Returns the last `limit` lines (default 100) per stream, or with `since` (the `cursor` from a previous call) or `from_start` the lines after that point, along with the next cursor and how many lines were evicted in between. Set `combined` to merge both streams chronologically with `timestamps` (\"iso\" by default, or \"relative\" milliseconds since start).")]
     async fn tail(&self, Parameters(req): Parameters<TailRequest>) -> Result<CallToolResult, ErrorData> {
-        let result = self.manager.tail(req.session_key);
+        let result = self.manager.tail(
+            req.session_key,
+            TailOptions {
+                since: req.since,
+                limit: req.limit,
+                from_start: req.from_start.unwrap_or(false),
+                combined: req.combined.unwrap_or(false),
+                timestamps: req.timestamps,
+            },
+        );
+        Ok(CallToolResult::success(vec![Content::text(result.to_string())]))
+    }
+
+    #[tool(description = "Search the full retained stdout/stderr buffers of one session (or all sessions) with a regex. Returns matching lines with their line numbers and `context` lines (default 2) before and after each match.")]
+    async fn search_logs(&self, Parameters(req): Parameters<SearchLogsRequest>) -> Result<CallToolResult, ErrorData> {
+        let pattern = match RegexBuilder::new(&req.pattern)
+            .case_insensitive(req.ignore_case.unwrap_or(false))
+            .build()
+        {
+            Ok(p) => p,
+            Err(e) => {
+                let result = json!({"error": format!("Invalid pattern: {}", e)});
+                return Ok(CallToolResult::success(vec![Content::text(result.to_string())]));
+            }
+        };
+        let result = self.manager.search_logs(
+            req.session_key,
+            pattern,
+            req.stream,
+            req.context.unwrap_or(DEFAULT_SEARCH_CONTEXT).min(MAX_SEARCH_CONTEXT),
+            req.max_matches.unwrap_or(DEFAULT_MAX_MATCHES).max(1),
+        );
+        Ok(CallToolResult::success(vec![Content::text(result.to_string())]))
+    }
+
+    #[tool(description = "Follow new stdout/stderr lines of a session until the timeout (default 30s, max 300s) elapses, a line matches pattern, or the process exits. Lines are streamed as progress notifications when the request carries a progress token, otherwise as logging notifications, and are also returned in the result.")]
+    async fn follow(
+        &self,
+        Parameters(req): Parameters<FollowRequest>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let pattern = match req.pattern.as_deref().map(Regex::new).transpose() {
+            Ok(p) => p,
+            Err(e) => {
+                let result = json!({"error": format!("Invalid pattern: {}", e)});
+                return Ok(CallToolResult::success(vec![Content::text(result.to_string())]));
+            }
+        };
+        let timeout = Duration::from_secs(req.timeout.unwrap_or(DEFAULT_FOLLOW_SECS).min(MAX_FOLLOW_SECS));
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<LogEvent>();
+        let peer = context.peer.clone();
+        let progress_token = context.meta.get_progress_token();
+        let session_key = req.session_key.clone();
+        let forwarder = tokio::spawn(async move {
+            let mut progress = 0.0;
+            while let Some(event) = rx.recv().await {
+                progress += 1.0;
+                let _ = match &progress_token {
+                    Some(token) => {
+                        peer.notify_progress(ProgressNotificationParam {
+                            progress_token: token.clone(),
+                            progress,
+                            total: None,
+                            message: Some(event.line),
+                        })
+                        .await
+                    }
+                    None => {
+                        peer.notify_logging_message(LoggingMessageNotificationParam {
+                            level: LoggingLevel::Info,
+                            logger: Some(format!("logs://{}/{}", session_key, event.stream.as_str())),
+                            data: json!(event.line),
+                        })
+                        .await
+                    }
+                };
+            }
+        });
+
+        let result = self
+            .manager
+            .follow(req.session_key, timeout, pattern, req.stream, Some(tx))
+            .await;
+        let _ = forwarder.await;
         Ok(CallToolResult::success(vec![Content::text(result.to_string())]))
     }
 }
@@ -79,7 +549,7 @@ impl ServerHandler for DevManagerService {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder().enable_tools().enable_logging().build(),
             server_info: Implementation::from_build_env(),
             instructions: Some(
                 "MCP Dev Server Manager - manages multiple development server sessions with automatic port allocation and log capture.".to_string()
@@ -0,0 +1,97 @@
+use crate::server_entry::LaunchSpec;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Serialize, Deserialize)]
+pub struct PersistedSession {
+    pub session_key: String,
+    pub pgid: i32,
+    pub port: u16,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra_ports: BTreeMap<String, u16>,
+    #[serde(flatten)]
+    pub spec: LaunchSpec,
+    pub started_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<PathBuf>,
+}
+
+impl PersistedSession {
+    pub fn started_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.started_at)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct PersistedState {
+    pub sessions: Vec<PersistedSession>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sticky_ports: BTreeMap<String, u16>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sticky_last_used: BTreeMap<String, u64>,
+}
+
+pub struct StateFile {
+    path: PathBuf,
+}
+
+impl StateFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    // One file per daemon port, so daemons running side by side keep separate registries
+    pub fn default_path(daemon_port: u16) -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/state")))?;
+        Some(base.join("dev-manager-mcp").join(format!("state-{}.json", daemon_port)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // logs/<state file name>/ next to the state file, so it is as daemon-specific as the file itself
+    pub fn log_dir(&self) -> PathBuf {
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        let name = self.path.file_stem().map(|s| s.to_os_string()).unwrap_or_else(|| "state".into());
+        dir.join("logs").join(name)
+    }
+
+    pub fn load(&self) -> anyhow::Result<PersistedState> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(PersistedState::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, state: &PersistedState) -> anyhow::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(state)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+        }
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+pub fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
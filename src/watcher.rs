@@ -0,0 +1,137 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Component, Path, PathBuf};
+use tokio::sync::mpsc;
+
+pub struct FileWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+pub fn glob_set(patterns: &[String]) -> anyhow::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        // `*` stays within one directory, as in a shell; `**` crosses them
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| anyhow::anyhow!("Invalid watch glob '{}': {}", pattern, e))?;
+        builder.add(glob);
+    }
+    Ok(builder.build()?)
+}
+
+// The directory part of a glob before its first wildcard, so `src/**/*.js` only watches `src`
+fn literal_base(root: &Path, pattern: &str) -> PathBuf {
+    let mut base = root.to_path_buf();
+    for component in Path::new(pattern).components() {
+        let part = match component {
+            Component::Normal(part) => part.to_string_lossy(),
+            _ => break,
+        };
+        if part.contains(['*', '?', '[', '{']) {
+            break;
+        }
+        base.push(part.as_ref());
+    }
+    base
+}
+
+// Directories are watched recursively. A single file is watched through its parent directory:
+// editors that save by renaming a temp file over it replace the inode a direct watch would follow.
+fn watch_target(root: &Path, pattern: &str) -> (PathBuf, RecursiveMode) {
+    let base = literal_base(root, pattern);
+    if base.is_dir() {
+        return (base, RecursiveMode::Recursive);
+    }
+    if base == root.join(pattern) {
+        if let Some(parent) = base.parent().filter(|p| p.is_dir()) {
+            return (parent.to_path_buf(), RecursiveMode::NonRecursive);
+        }
+    }
+    (root.to_path_buf(), RecursiveMode::Recursive)
+}
+
+pub fn watch(root: &Path, patterns: &[String]) -> anyhow::Result<(FileWatcher, mpsc::UnboundedReceiver<PathBuf>)> {
+    let globs = glob_set(patterns)?;
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    let event_root = root.to_path_buf();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let event = match res {
+            Ok(event) => event,
+            Err(_) => return,
+        };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+            return;
+        }
+        for path in event.paths {
+            let relative = path.strip_prefix(&event_root).unwrap_or(&path);
+            if globs.is_match(relative) {
+                let _ = tx.send(relative.to_path_buf());
+            }
+        }
+    })?;
+
+    let mut targets: Vec<(PathBuf, RecursiveMode)> = patterns.iter().map(|p| watch_target(root, p)).collect();
+    // Recursive watches first, parents before children, so anything they cover can be skipped
+    targets.sort_by_key(|(target, mode)| (*mode != RecursiveMode::Recursive, target.clone()));
+    let mut watched: Vec<(PathBuf, RecursiveMode)> = Vec::new();
+    for (target, mode) in targets {
+        let covered = watched.iter().any(|(w, m)| match m {
+            RecursiveMode::Recursive => target.starts_with(w),
+            RecursiveMode::NonRecursive => *w == target,
+        });
+        if covered {
+            continue;
+        }
+        watcher.watch(&target, mode)?;
+        watched.push((target, mode));
+    }
+
+    Ok((FileWatcher { _watcher: watcher }, rx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watches_the_literal_base_of_each_glob() {
+        let root = std::env::temp_dir().join(format!("dev-manager-watch-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+
+        assert_eq!(
+            watch_target(&root, "src/**/*.js"),
+            (root.join("src"), RecursiveMode::Recursive)
+        );
+        assert_eq!(
+            watch_target(&root, "**/*.js"),
+            (root.clone(), RecursiveMode::Recursive)
+        );
+        // Single files are watched through their directory so rename-on-save is seen
+        assert_eq!(
+            watch_target(&root, "go.mod"),
+            (root.clone(), RecursiveMode::NonRecursive)
+        );
+        assert_eq!(
+            watch_target(&root, "src/index.js"),
+            (root.join("src"), RecursiveMode::NonRecursive)
+        );
+        // A directory that does not exist yet falls back to the whole root
+        assert_eq!(
+            watch_target(&root, "lib/**/*.js"),
+            (root.clone(), RecursiveMode::Recursive)
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn single_star_does_not_cross_directories() {
+        let globs = glob_set(&["*.js".to_string(), "src/**/*.ts".to_string()]).unwrap();
+        assert!(globs.is_match("index.js"));
+        assert!(!globs.is_match("node_modules/pkg/index.js"));
+        assert!(globs.is_match("src/index.ts"));
+        assert!(globs.is_match("src/routes/api/users.ts"));
+    }
+}